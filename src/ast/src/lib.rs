@@ -1,6 +1,6 @@
 use token::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct UntypedProgram {
     pub stmts: Vec<Stmt>,
 }
@@ -19,16 +19,339 @@ impl UntypedProgram {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Stmt {
-    Expr { e: Expr },
+    Expr {
+        e: Expr,
+    },
+    Let {
+        name: Token,
+        ty: Option<TypeExpr>,
+        initializer: Expr,
+    },
+    Mut {
+        name: Token,
+        ty: Option<TypeExpr>,
+        initializer: Expr,
+    },
+    Fn {
+        decl: FnDecl,
+    },
+    Record {
+        decl: RecordDecl,
+    },
+    Enum {
+        decl: EnumDecl,
+    },
+    Interface {
+        decl: InterfaceDecl,
+    },
+    Impl {
+        decl: ImplDecl,
+    },
+    Check {
+        decl: CheckDecl,
+    },
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FnDecl {
+    pub name: Token,
+    pub params: Vec<Param>,
+    /// `None` if the function doesn't produce a value.
+    pub return_type: Option<TypeExpr>,
+    pub body: Block,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param {
+    pub name: Token,
+    pub ty: TypeExpr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordDecl {
+    pub name: Token,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name: Token,
+    pub ty: TypeExpr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumDecl {
+    pub name: Token,
+    pub variants: Vec<Variant>,
+}
+
+/// A single case of an enum. Variants without any fields are unit variants.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant {
+    pub name: Token,
+    pub fields: Vec<Field>,
+}
+
+/// A function's name & type, without a body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FnSignature {
+    pub name: Token,
+    pub params: Vec<Param>,
+    /// `None` if the function doesn't produce a value.
+    pub return_type: Option<TypeExpr>,
+}
+
+/// The methods which a type must have to conform to the interface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceDecl {
+    pub name: Token,
+    pub methods: Vec<FnSignature>,
+}
+
+/// Methods for the type named `type_name`. Within a method's body, `self`
+/// refers to the value the method was called on.
+///
+/// If `interface` is given then the methods must be exactly those which the
+/// interface requires.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImplDecl {
+    pub type_name: Token,
+    pub interface: Option<Token>,
+    pub methods: Vec<FnDecl>,
+}
+
+/// A predicate on values of the `subject` type, which a refinement type can
+/// name to only admit the values passing it. Checks run while type checking,
+/// so they must be pure & total.
+///
+/// Within the body, `value` is the value being checked, & `okay` & `error`
+/// are the two possible outcomes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckDecl {
+    pub name: Token,
+    pub subject: TypeExpr,
+    pub params: Vec<Param>,
+    pub body: Block,
+}
+
+/// A braced sequence of statements, optionally followed by an expression
+/// without a trailing `;` which gives the block its value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub tail: Option<Box<Expr>>,
+    pub span: Span,
+}
+
+/// A type as written in the source, e.g. in a binding's annotation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeExpr {
+    Named {
+        name: Token,
+    },
+    /// `base checking check(args)`, i.e. the values of `base` which pass
+    /// `check`. The arguments may be omitted along with their parentheses.
+    Checking {
+        base: Box<TypeExpr>,
+        check: Token,
+        args: Vec<Expr>,
+        span: Span,
+    },
+}
+
+impl TypeExpr {
+    pub fn named<S: Into<String>>(s: S) -> TypeExpr {
+        TypeExpr::Named {
+            name: Token::identifier(s),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            TypeExpr::Named { name } => name.span,
+            TypeExpr::Checking { span, .. } => *span,
+        }
+    }
+
+    /// Whether `self` & `other` name the same type, wherever each of them
+    /// is written.
+    pub fn same_as(&self, other: &TypeExpr) -> bool {
+        match (self, other) {
+            (TypeExpr::Named { name: a }, TypeExpr::Named { name: b }) => a.lexeme == b.lexeme,
+            (
+                TypeExpr::Checking {
+                    base: a,
+                    check: c,
+                    args: x,
+                    ..
+                },
+                TypeExpr::Checking {
+                    base: b,
+                    check: d,
+                    args: y,
+                    ..
+                },
+            ) => {
+                a.same_as(b)
+                    && c.lexeme == d.lexeme
+                    && x.len() == y.len()
+                    && x.iter().zip(y).all(|(x, y)| same_literal(x, y))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Whether `a` & `b` are the same literal, possibly negated. The arguments
+/// of a check must be literals, so no other expressions are the same.
+fn same_literal(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::Literal { l: a, .. }, Expr::Literal { l: b, .. }) => a == b,
+        (Expr::Unary { op: o, right: a }, Expr::Unary { op: p, right: b }) => {
+            o.kind == p.kind && same_literal(a, b)
+        }
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Expr {
-    Identifier { name: Token },
-    Literal { l: Literal },
-    FunctionApplication { callee: Box<Expr>, args: Vec<Expr> },
+    Identifier {
+        name: Token,
+    },
+    Literal {
+        l: Literal,
+        span: Span,
+    },
+    FunctionApplication {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+        span: Span,
+    },
+    Unary {
+        op: Token,
+        right: Box<Expr>,
+    },
+    Binary {
+        left: Box<Expr>,
+        op: Token,
+        right: Box<Expr>,
+    },
+    Assign {
+        name: Token,
+        value: Box<Expr>,
+    },
+    Block {
+        block: Block,
+    },
+    If {
+        keyword: Token,
+        condition: Box<Expr>,
+        then_branch: Block,
+        /// Either a `Block` or, for `else if`, another `If`.
+        else_branch: Option<Box<Expr>>,
+    },
+    While {
+        keyword: Token,
+        condition: Box<Expr>,
+        body: Block,
+    },
+    RecordLiteral {
+        name: Token,
+        fields: Vec<FieldInit>,
+        span: Span,
+    },
+    FieldAccess {
+        object: Box<Expr>,
+        name: Token,
+    },
+    MethodCall {
+        receiver: Box<Expr>,
+        method: Token,
+        args: Vec<Expr>,
+        span: Span,
+    },
+    Tuple {
+        elements: Vec<Expr>,
+        span: Span,
+    },
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<MatchArm>,
+        span: Span,
+    },
+}
+
+/// The value given to a single field in a record literal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldInit {
+    pub name: Token,
+    pub value: Expr,
+}
+
+/// A single `pattern => body` case of a `match` expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// `_`, which matches anything without binding it.
+    Wildcard {
+        span: Span,
+    },
+    Literal {
+        l: Literal,
+        span: Span,
+    },
+    /// A bare name. This matches a unit variant of the same name if the
+    /// value being matched is an enum with such a variant, and otherwise
+    /// binds the value to the name.
+    Identifier {
+        name: Token,
+    },
+    /// A variant, optionally qualified by its enum's name, e.g.
+    /// `CoffeeMaker.aeropress(inverted)`. Without parentheses the variant's
+    /// fields aren't matched against.
+    Variant {
+        enum_name: Option<Token>,
+        name: Token,
+        fields: Option<Vec<Pattern>>,
+        span: Span,
+    },
+    Tuple {
+        elements: Vec<Pattern>,
+        span: Span,
+    },
+    Or {
+        alternatives: Vec<Pattern>,
+    },
+}
+
+impl Pattern {
+    pub fn identifier<S: Into<String>>(s: S) -> Pattern {
+        Pattern::Identifier {
+            name: Token::identifier(s),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard { span } => *span,
+            Pattern::Literal { span, .. } => *span,
+            Pattern::Identifier { name } => name.span,
+            Pattern::Variant { span, .. } => *span,
+            Pattern::Tuple { span, .. } => *span,
+            Pattern::Or { alternatives } => {
+                let first = alternatives[0].span();
+                first.to(alternatives[alternatives.len() - 1].span())
+            }
+        }
+    }
 }
 
 impl Expr {
@@ -37,6 +360,34 @@ impl Expr {
             name: Token::identifier(s.into()),
         }
     }
+
+    /// The region of source from which this expression was parsed.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Identifier { name } => name.span,
+            Expr::Literal { span, .. } => *span,
+            Expr::FunctionApplication { span, .. } => *span,
+            Expr::Unary { op, right } => op.span.to(right.span()),
+            Expr::Binary { left, right, .. } => left.span().to(right.span()),
+            Expr::Assign { name, value } => name.span.to(value.span()),
+            Expr::Block { block } => block.span,
+            Expr::If {
+                keyword,
+                then_branch,
+                else_branch,
+                ..
+            } => match else_branch {
+                Some(e) => keyword.span.to(e.span()),
+                None => keyword.span.to(then_branch.span),
+            },
+            Expr::While { keyword, body, .. } => keyword.span.to(body.span),
+            Expr::RecordLiteral { span, .. } => *span,
+            Expr::FieldAccess { object, name } => object.span().to(name.span),
+            Expr::MethodCall { span, .. } => *span,
+            Expr::Tuple { span, .. } => *span,
+            Expr::Match { span, .. } => *span,
+        }
+    }
 }
 
 // TODO: this is a bad idea, since at the callsite
@@ -44,31 +395,82 @@ impl Expr {
 // an identifier rather than a literal.
 impl<T: Into<Literal>> From<T> for Expr {
     fn from(l: T) -> Expr {
-        Expr::Literal { l: l.into() }
+        Expr::Literal {
+            l: l.into(),
+            span: Span::default(),
+        }
     }
 }
 
 pub trait ExprVisitor<V> {
     fn visit_identifier(&mut self, name: &Token) -> V;
     fn visit_literal(&mut self, l: &Literal) -> V;
-    fn visit_function_application(&mut self, callee: &Expr, args: &[Expr]) -> V;
+    fn visit_function_application(&mut self, callee: &Expr, args: &[Expr], span: Span) -> V;
+    fn visit_unary(&mut self, op: &Token, right: &Expr) -> V;
+    fn visit_binary(&mut self, left: &Expr, op: &Token, right: &Expr) -> V;
+    fn visit_assign(&mut self, name: &Token, value: &Expr) -> V;
+    fn visit_block(&mut self, block: &Block) -> V;
+    fn visit_if(&mut self, condition: &Expr, then_branch: &Block, else_branch: Option<&Expr>) -> V;
+    fn visit_while(&mut self, condition: &Expr, body: &Block) -> V;
+    fn visit_record_literal(&mut self, name: &Token, fields: &[FieldInit], span: Span) -> V;
+    fn visit_field_access(&mut self, object: &Expr, name: &Token) -> V;
+    fn visit_method_call(
+        &mut self,
+        receiver: &Expr,
+        method: &Token,
+        args: &[Expr],
+        span: Span,
+    ) -> V;
+    fn visit_tuple(&mut self, elements: &[Expr]) -> V;
+    fn visit_match(&mut self, scrutinee: &Expr, arms: &[MatchArm], span: Span) -> V;
 }
 
 impl Expr {
     pub fn accept<Val, Visitor: ExprVisitor<Val>>(&self, visitor: &mut Visitor) -> Val {
         match self {
             Expr::Identifier { name } => visitor.visit_identifier(name),
-            Expr::Literal { l } => visitor.visit_literal(l),
-            Expr::FunctionApplication { callee, args } => {
-                visitor.visit_function_application(callee, args)
+            Expr::Literal { l, .. } => visitor.visit_literal(l),
+            Expr::FunctionApplication { callee, args, span } => {
+                visitor.visit_function_application(callee, args, *span)
             }
+            Expr::Unary { op, right } => visitor.visit_unary(op, right),
+            Expr::Binary { left, op, right } => visitor.visit_binary(left, op, right),
+            Expr::Assign { name, value } => visitor.visit_assign(name, value),
+            Expr::Block { block } => visitor.visit_block(block),
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => visitor.visit_if(condition, then_branch, else_branch.as_deref()),
+            Expr::While {
+                condition, body, ..
+            } => visitor.visit_while(condition, body),
+            Expr::RecordLiteral { name, fields, span } => {
+                visitor.visit_record_literal(name, fields, *span)
+            }
+            Expr::FieldAccess { object, name } => visitor.visit_field_access(object, name),
+            Expr::MethodCall {
+                receiver,
+                method,
+                args,
+                span,
+            } => visitor.visit_method_call(receiver, method, args, *span),
+            Expr::Tuple { elements, .. } => visitor.visit_tuple(elements),
+            Expr::Match {
+                scrutinee,
+                arms,
+                span,
+            } => visitor.visit_match(scrutinee, arms, *span),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
     String(String),
+    Int(i64),
+    Bool(bool),
 }
 
 impl Literal {
@@ -77,8 +479,26 @@ impl Literal {
     }
 }
 
-impl<T: Into<String>> From<T> for Literal {
-    fn from(t: T) -> Literal {
-        Literal::String(t.into())
+impl From<&str> for Literal {
+    fn from(s: &str) -> Literal {
+        Literal::String(s.into())
+    }
+}
+
+impl From<String> for Literal {
+    fn from(s: String) -> Literal {
+        Literal::String(s)
+    }
+}
+
+impl From<i64> for Literal {
+    fn from(i: i64) -> Literal {
+        Literal::Int(i)
+    }
+}
+
+impl From<bool> for Literal {
+    fn from(b: bool) -> Literal {
+        Literal::Bool(b)
     }
 }
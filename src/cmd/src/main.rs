@@ -2,6 +2,7 @@ use clap::{AppSettings, Parser};
 use interpreter::interpret;
 use lexer::*;
 use parser::*;
+use typechecker::{typecheck, TypedProgram};
 
 #[derive(Parser)]
 #[clap(
@@ -38,18 +39,39 @@ fn main() {
             // TODO: don't read the whole file in at once.
             let contents = std::fs::read_to_string(path).expect("Could not read provided file.");
             let tokens = token_stream(contents.as_str());
-            let program = parse(tokens);
+            let program = parse_or_exit(tokens);
             println!("{}", program.pretty_printed());
         }
         Invocation::Run { path } => {
             let contents = std::fs::read_to_string(path).expect("Could not read provided file.");
             let tokens = token_stream(contents.as_str());
-            let program = parse(tokens);
-            interpret(program, std::io::stdout());
+            let program = typecheck_or_exit(parse_or_exit(tokens));
+            if let Err(e) = interpret(program.into_untyped(), std::io::stdout()) {
+                eprintln!("{}", e);
+                std::process::exit(1)
+            }
         }
     }
 }
 
+fn parse_or_exit(tokens: TokenStream) -> ast::UntypedProgram {
+    parse(tokens).unwrap_or_else(|errs| {
+        for e in errs {
+            eprintln!("{}", e);
+        }
+        std::process::exit(1)
+    })
+}
+
+fn typecheck_or_exit(program: ast::UntypedProgram) -> TypedProgram {
+    typecheck(program).unwrap_or_else(|errs| {
+        for e in errs {
+            eprintln!("{}", e);
+        }
+        std::process::exit(1)
+    })
+}
+
 #[test]
 fn verify_clap_config() {
     use clap::IntoApp;
@@ -0,0 +1,63 @@
+use crate::Value;
+
+/// The methods which every value of a built-in type has, along with the
+/// types of their arguments.
+const NATIVE_METHODS: &[(&str, &str, &[&str])] = &[
+    ("String", "appending", &["String"]),
+    ("String", "is_empty", &[]),
+    ("String", "length", &[]),
+    ("String", "to_string", &[]),
+    ("Int", "to_string", &[]),
+    ("Bool", "to_string", &[]),
+];
+
+pub(crate) fn has_native_method(receiver: &Value, method: &str) -> bool {
+    signature(receiver, method).is_some()
+}
+
+/// Calls one of the built-in methods, assuming that `receiver` has a method
+/// of that name.
+pub(crate) fn call_native_method(
+    receiver: &Value,
+    method: &str,
+    args: &[Value],
+) -> Result<Value, String> {
+    let params = signature(receiver, method).expect("Native method should exist");
+    let qualified_name = format!("{}.{}", receiver.type_name(), method);
+    if args.len() != params.len() {
+        return Err(format!(
+            "{} expects {} argument(s) but was given {}",
+            qualified_name,
+            params.len(),
+            args.len()
+        ));
+    }
+    for (param, arg) in params.iter().zip(args) {
+        if *param != arg.type_name() {
+            return Err(format!(
+                "{} expects a {} argument, found {}",
+                qualified_name,
+                param,
+                arg.type_name()
+            ));
+        }
+    }
+
+    let result = match (receiver, method, args) {
+        (Value::String(s), "appending", [Value::String(suffix)]) => {
+            Value::String(format!("{}{}", s, suffix))
+        }
+        (Value::String(s), "is_empty", []) => Value::Bool(s.is_empty()),
+        (Value::String(s), "length", []) => Value::Int(s.chars().count() as i64),
+        (v, "to_string", []) => Value::String(v.to_string()),
+        _ => unreachable!("Arguments were checked against the method's signature"),
+    };
+    Ok(result)
+}
+
+fn signature(receiver: &Value, method: &str) -> Option<&'static [&'static str]> {
+    NATIVE_METHODS
+        .iter()
+        .find(|(ty, name, _)| *ty == receiver.type_name() && *name == method)
+        .map(|(_, _, params)| *params)
+}
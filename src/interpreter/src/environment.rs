@@ -0,0 +1,69 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::Value;
+
+/// A single scope of bindings, which defers to the scope enclosing it for
+/// any names it doesn't define itself.
+pub(crate) struct Environment {
+    bindings: HashMap<String, Binding>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+struct Binding {
+    value: Value,
+    mutable: bool,
+}
+
+pub(crate) enum AssignError {
+    Undefined,
+    Immutable,
+}
+
+impl Environment {
+    pub(crate) fn global() -> Environment {
+        Environment {
+            bindings: HashMap::new(),
+            enclosing: None,
+        }
+    }
+
+    pub(crate) fn enclosed_by(enclosing: Rc<RefCell<Environment>>) -> Environment {
+        Environment {
+            bindings: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    /// Binds `name` in this scope, shadowing any existing binding.
+    pub(crate) fn define<S: Into<String>>(&mut self, name: S, value: Value, mutable: bool) {
+        self.bindings
+            .insert(name.into(), Binding { value, mutable });
+    }
+
+    /// Whether `name` is bound in this scope itself, ignoring any enclosing
+    /// scope.
+    pub(crate) fn defines(&self, name: &str) -> bool {
+        self.bindings.contains_key(name)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Value> {
+        match self.bindings.get(name) {
+            Some(b) => Some(b.value.clone()),
+            None => self.enclosing.as_ref()?.borrow().get(name),
+        }
+    }
+
+    pub(crate) fn assign(&mut self, name: &str, value: Value) -> Result<(), AssignError> {
+        match self.bindings.get_mut(name) {
+            Some(b) if b.mutable => {
+                b.value = value;
+                Ok(())
+            }
+            Some(_) => Err(AssignError::Immutable),
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+                None => Err(AssignError::Undefined),
+            },
+        }
+    }
+}
@@ -1,103 +1,1148 @@
-use std::{cell::RefCell, collections::HashMap, io::Write, rc::Rc};
+mod builtins;
+mod environment;
 
-use ast::{Expr, ExprVisitor, Literal, UntypedProgram};
-use token::Token;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt,
+    io::Write,
+    rc::Rc,
+};
 
-pub fn interpret<W: Write + 'static>(p: UntypedProgram, out: W) {
+use ast::{
+    Block, CheckDecl, EnumDecl, Expr, ExprVisitor, FieldInit, FnDecl, ImplDecl, InterfaceDecl,
+    Literal, MatchArm, Param, Pattern, RecordDecl, Stmt, TypeExpr, UntypedProgram, Variant,
+};
+use environment::{AssignError, Environment};
+use token::{Span, Token, TokenKind};
+
+pub fn interpret<W: Write + 'static>(p: UntypedProgram, out: W) -> Result<(), EiRuntimeError> {
     let mut interpreter = Interpreter::new(Rc::new(RefCell::new(out)));
-    interpreter.interpret(p);
+    interpreter.interpret(p)
 }
 
-struct NativeFn {
-    body: Box<dyn FnMut(&[Value]) -> Value>,
+/// Describes why a program stopped before it finished running.
+#[derive(Debug, PartialEq)]
+pub struct EiRuntimeError {
+    pub message: String,
+    /// The location of the expression which could not be evaluated.
+    pub span: Span,
 }
 
-struct Globals {
-    ns: HashMap<String, NativeFn>,
+impl EiRuntimeError {
+    fn new<S: Into<String>>(message: S, span: Span) -> EiRuntimeError {
+        EiRuntimeError {
+            message: message.into(),
+            span,
+        }
+    }
 }
 
-impl<'program> Globals {
-    fn new() -> Globals {
-        Globals { ns: HashMap::new() }
+impl fmt::Display for EiRuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
     }
+}
+
+type EvalResult = Result<Value, EiRuntimeError>;
 
-    fn add<S: Into<String>>(&mut self, k: S, v: NativeFn) {
-        self.ns.insert(k.into(), v);
+// Native functions don't know where they were called from, so they just
+// describe what went wrong & let the caller attach a location.
+type NativeBody = Box<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+struct NativeFn {
+    name: String,
+    body: NativeBody,
+}
+
+impl fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
     }
+}
 
-    fn call(&mut self, name: &str, args: &[Value]) -> Value {
-        let func = self
-            .ns
-            .get_mut(name)
-            .expect(&format!("No such function {}", name));
-        (func.body)(args)
+/// A function declared in an Ei program, along with the scope it was
+/// declared in.
+struct Function {
+    decl: FnDecl,
+    closure: Rc<RefCell<Environment>>,
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.decl.name.lexeme)
     }
 }
 
+/// An instance of a record type, with its fields in declaration order.
 #[derive(Debug)]
+struct Record {
+    type_name: String,
+    fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    fn get(&self, field: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, v)| v)
+    }
+}
+
+/// An instance of one of an enum's variants, with the variant's fields in
+/// declaration order.
+struct Enum {
+    decl: Rc<EnumDecl>,
+    variant: String,
+    fields: Vec<(String, Value)>,
+}
+
+impl Enum {
+    fn type_name(&self) -> &str {
+        &self.decl.name.lexeme
+    }
+
+    fn has_variant(&self, name: &str) -> bool {
+        self.decl.variants.iter().any(|v| v.name.lexeme == name)
+    }
+}
+
+impl fmt::Debug for Enum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Enum")
+            .field("type_name", &self.type_name())
+            .field("variant", &self.variant)
+            .field("fields", &self.fields)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug)]
 enum Value {
-    GlobalFn(String),
+    NativeFn(Rc<NativeFn>),
+    Fn(Rc<Function>),
+    RecordType(Rc<RecordDecl>),
+    Record(Rc<Record>),
+    EnumType(Rc<EnumDecl>),
+    Enum(Rc<Enum>),
+    InterfaceType(Rc<InterfaceDecl>),
+    Tuple(Vec<Value>),
     String(String),
+    Int(i64),
+    Bool(bool),
     Void,
 }
 
+impl Value {
+    fn type_name(&self) -> &str {
+        match self {
+            Value::NativeFn(_) | Value::Fn(_) => "Fn",
+            Value::RecordType(_) | Value::EnumType(_) | Value::InterfaceType(_) => "Type",
+            Value::Record(record) => &record.type_name,
+            Value::Enum(e) => e.type_name(),
+            Value::Tuple(_) => "Tuple",
+            Value::String(_) => "String",
+            Value::Int(_) => "Int",
+            Value::Bool(_) => "Bool",
+            Value::Void => "Void",
+        }
+    }
+
+    /// Returns `None` if the two values can't be compared.
+    fn equals(&self, other: &Value) -> Option<bool> {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => Some(a == b),
+            (Value::Int(a), Value::Int(b)) => Some(a == b),
+            (Value::Bool(a), Value::Bool(b)) => Some(a == b),
+            (Value::Void, Value::Void) => Some(true),
+            (Value::Record(a), Value::Record(b)) if a.type_name == b.type_name => {
+                fields_equal(&a.fields, &b.fields)
+            }
+            (Value::Enum(a), Value::Enum(b)) if a.type_name() == b.type_name() => {
+                if a.variant == b.variant {
+                    fields_equal(&a.fields, &b.fields)
+                } else {
+                    Some(false)
+                }
+            }
+            (Value::Tuple(a), Value::Tuple(b)) if a.len() == b.len() => {
+                let mut equal = true;
+                for (a, b) in a.iter().zip(b) {
+                    equal &= a.equals(b)?;
+                }
+                Some(equal)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn fields_equal(a: &[(String, Value)], b: &[(String, Value)]) -> Option<bool> {
+    let mut equal = true;
+    for ((_, a), (_, b)) in a.iter().zip(b) {
+        equal &= a.equals(b)?;
+    }
+    Some(equal)
+}
+
+fn write_fields(f: &mut fmt::Formatter<'_>, fields: &[(String, Value)]) -> fmt::Result {
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}: ", name)?;
+        write_nested(f, value)?;
+    }
+    Ok(())
+}
+
+/// Writes a value which is part of a larger one, quoting strings so that
+/// they're distinguishable from other values.
+fn write_nested(f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+    match value {
+        Value::String(s) => write!(f, "{:?}", s),
+        v => write!(f, "{}", v),
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::NativeFn(native) => write!(f, "{:?}", native),
+            Value::Fn(function) => write!(f, "{:?}", function),
+            Value::RecordType(decl) => write!(f, "<record {}>", decl.name.lexeme),
+            Value::Record(record) if record.fields.is_empty() => {
+                write!(f, "{} {{}}", record.type_name)
+            }
+            Value::Record(record) => {
+                write!(f, "{} {{ ", record.type_name)?;
+                write_fields(f, &record.fields)?;
+                write!(f, " }}")
+            }
+            Value::EnumType(decl) => write!(f, "<enum {}>", decl.name.lexeme),
+            Value::InterfaceType(decl) => write!(f, "<interface {}>", decl.name.lexeme),
+            Value::Enum(e) if e.fields.is_empty() => {
+                write!(f, "{}.{}", e.type_name(), e.variant)
+            }
+            Value::Enum(e) => {
+                write!(f, "{}.{}(", e.type_name(), e.variant)?;
+                write_fields(f, &e.fields)?;
+                write!(f, ")")
+            }
+            Value::Tuple(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_nested(f, element)?;
+                }
+                write!(f, ")")
+            }
+            Value::String(s) => write!(f, "{}", s),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Void => write!(f, "()"),
+        }
+    }
+}
+
+// Deep enough for any reasonable program, while shallow enough that we
+// report runaway recursion rather than overflowing the host's stack.
+const MAX_CALL_DEPTH: usize = 200;
+
 struct Interpreter {
-    globals: Globals,
+    environment: Rc<RefCell<Environment>>,
+    /// The methods declared in `impl` blocks, by type name & then by name.
+    methods: HashMap<String, HashMap<String, Rc<Function>>>,
+    call_depth: usize,
 }
 
 impl Interpreter {
     fn new<W: Write + 'static>(write: Rc<RefCell<W>>) -> Interpreter {
-        let mut globals = Globals::new();
-
-        globals.add(
-            "print_ln",
-            NativeFn {
-                body: Box::new(move |args| {
-                    let s = match args {
-                        [Value::String(s)] => s,
-                        _ => panic!("Incorrect arguments for print_ln"),
-                    };
-                    writeln!(write.borrow_mut(), "{}", s).expect("Oh no!");
-                    Value::Void
-                }),
-            },
-        );
+        let mut globals = Environment::global();
+
+        let print_ln = NativeFn {
+            name: "print_ln".into(),
+            body: Box::new(move |args| {
+                let v = match args {
+                    [v] => v,
+                    _ => return Err(format!("Incorrect arguments for print_ln: {:?}", args)),
+                };
+                writeln!(write.borrow_mut(), "{}", v)
+                    .map_err(|e| format!("Could not write output: {}", e))?;
+                Ok(Value::Void)
+            }),
+        };
+        globals.define("print_ln", Value::NativeFn(Rc::new(print_ln)), false);
+
+        Interpreter {
+            environment: Rc::new(RefCell::new(globals)),
+            methods: HashMap::new(),
+            call_depth: 0,
+        }
+    }
+
+    fn interpret(&mut self, p: UntypedProgram) -> Result<(), EiRuntimeError> {
+        self.execute_all(&p.stmts)
+    }
+
+    /// Runs a sequence of statements which share a scope. Types, functions &
+    /// methods are visible throughout the scope they're declared in, so
+    /// they're all declared before any other statement runs, e.g. a function
+    /// can be called above its declaration. `let` & `mut` bindings still run
+    /// in order. This matches the type checker, which declares the same
+    /// items first, so a well-typed program never refers to one too early.
+    ///
+    /// Leaves the interpreter in the scope of the last statement, where a
+    /// block's tail is evaluated.
+    fn execute_all(&mut self, stmts: &[Stmt]) -> Result<(), EiRuntimeError> {
+        let scopes = self.statement_scopes(stmts);
+        let (impls, declarations): (Vec<_>, Vec<_>) = stmts
+            .iter()
+            .zip(&scopes)
+            .filter(|(s, _)| is_declaration(s))
+            .partition(|(s, _)| matches!(s, Stmt::Impl { .. }));
+        // Methods can only be declared for types which already exist.
+        for (s, scope) in declarations.into_iter().chain(impls) {
+            self.declare(s, scope)?;
+        }
+        for (s, scope) in stmts.iter().zip(scopes) {
+            if !is_declaration(s) {
+                self.environment = scope;
+                self.execute(s)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The scope in which each of `stmts` runs. A `let` or `mut` which
+    /// shadows a name already bound in the current scope begins a new scope
+    /// nested in it, so that a function declared earlier still sees the
+    /// binding it was declared alongside, as the type checker does. These
+    /// are all created up front, as functions are declared before anything
+    /// runs.
+    fn statement_scopes(&self, stmts: &[Stmt]) -> Vec<Rc<RefCell<Environment>>> {
+        let mut bound: HashSet<&str> = stmts.iter().filter_map(declared_name).collect();
+        let mut scope = Rc::clone(&self.environment);
+        stmts
+            .iter()
+            .map(|s| {
+                if let Stmt::Let { name, .. } | Stmt::Mut { name, .. } = s {
+                    let shadows = !bound.insert(&name.lexeme)
+                        || self.environment.borrow().defines(&name.lexeme);
+                    if shadows {
+                        let nested = Environment::enclosed_by(Rc::clone(&scope));
+                        scope = Rc::new(RefCell::new(nested));
+                    }
+                }
+                Rc::clone(&scope)
+            })
+            .collect()
+    }
+
+    fn execute(&mut self, s: &Stmt) -> Result<(), EiRuntimeError> {
+        match s {
+            Stmt::Expr { e } => {
+                e.accept(self)?;
+            }
+            Stmt::Let {
+                name, initializer, ..
+            } => {
+                let value = initializer.accept(self)?;
+                self.environment
+                    .borrow_mut()
+                    .define(&name.lexeme, value, false);
+            }
+            Stmt::Mut {
+                name, initializer, ..
+            } => {
+                let value = initializer.accept(self)?;
+                self.environment
+                    .borrow_mut()
+                    .define(&name.lexeme, value, true);
+            }
+            _ => unreachable!("Declarations are made before any other statement runs"),
+        }
+        Ok(())
+    }
+
+    /// Declares the item `s` in the current scope. Functions & methods
+    /// close over `scope`, the scope in which `s` was written.
+    fn declare(
+        &mut self,
+        s: &Stmt,
+        scope: &Rc<RefCell<Environment>>,
+    ) -> Result<(), EiRuntimeError> {
+        match s {
+            Stmt::Fn { decl } => {
+                let function = Function {
+                    decl: decl.clone(),
+                    closure: Rc::clone(scope),
+                };
+                self.environment.borrow_mut().define(
+                    &decl.name.lexeme,
+                    Value::Fn(Rc::new(function)),
+                    false,
+                );
+            }
+            Stmt::Record { decl } => {
+                self.environment.borrow_mut().define(
+                    &decl.name.lexeme,
+                    Value::RecordType(Rc::new(decl.clone())),
+                    false,
+                );
+            }
+            Stmt::Enum { decl } => {
+                self.environment.borrow_mut().define(
+                    &decl.name.lexeme,
+                    Value::EnumType(Rc::new(decl.clone())),
+                    false,
+                );
+            }
+            Stmt::Interface { decl } => {
+                self.environment.borrow_mut().define(
+                    &decl.name.lexeme,
+                    Value::InterfaceType(Rc::new(decl.clone())),
+                    false,
+                );
+            }
+            Stmt::Impl { decl } => self.declare_methods(decl, scope)?,
+            Stmt::Check { decl } => {
+                let mut outcomes = Environment::enclosed_by(Rc::clone(&self.environment));
+                outcomes.define("okay", Value::Bool(true), false);
+                outcomes.define("error", Value::Bool(false), false);
+                let function = Function {
+                    decl: check_function(decl),
+                    closure: Rc::new(RefCell::new(outcomes)),
+                };
+                self.environment.borrow_mut().define(
+                    &decl.name.lexeme,
+                    Value::Fn(Rc::new(function)),
+                    false,
+                );
+            }
+            Stmt::Expr { .. } | Stmt::Let { .. } | Stmt::Mut { .. } => {
+                unreachable!("Only declarations are hoisted")
+            }
+        }
+        Ok(())
+    }
+
+    fn declare_methods(
+        &mut self,
+        decl: &ImplDecl,
+        scope: &Rc<RefCell<Environment>>,
+    ) -> Result<(), EiRuntimeError> {
+        let type_name = &decl.type_name.lexeme;
+        let is_type = match type_name.as_str() {
+            "String" | "Int" | "Bool" => true,
+            _ => matches!(
+                self.environment.borrow().get(type_name),
+                Some(Value::RecordType(_) | Value::EnumType(_))
+            ),
+        };
+        if !is_type {
+            return Err(EiRuntimeError::new(
+                format!("Cannot implement methods for unknown type {}", type_name),
+                decl.type_name.span,
+            ));
+        }
+
+        if let Some(interface) = &decl.interface {
+            match self.environment.borrow().get(&interface.lexeme) {
+                Some(Value::InterfaceType(required)) => check_conformance(decl, &required)?,
+                _ => {
+                    return Err(EiRuntimeError::new(
+                        format!("Unknown interface {}", interface.lexeme),
+                        interface.span,
+                    ))
+                }
+            }
+        }
+
+        let methods = self.methods.entry(type_name.clone()).or_default();
+        for method in &decl.methods {
+            let function = Function {
+                decl: method.clone(),
+                closure: Rc::clone(scope),
+            };
+            methods.insert(method.name.lexeme.clone(), Rc::new(function));
+        }
+        Ok(())
+    }
+
+    /// Runs `block` in the scope `env`, restoring the current scope after.
+    fn execute_block(&mut self, block: &Block, env: Environment) -> EvalResult {
+        self.with_environment(env, |i| i.block_value(block))
+    }
+
+    fn with_environment<R>(&mut self, env: Environment, f: impl FnOnce(&mut Self) -> R) -> R {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(env)));
+        let result = f(self);
+        self.environment = previous;
+        result
+    }
+
+    fn block_value(&mut self, block: &Block) -> EvalResult {
+        self.execute_all(&block.stmts)?;
+        match &block.tail {
+            Some(e) => e.accept(self),
+            None => Ok(Value::Void),
+        }
+    }
+
+    /// Calls `function`, binding `self` to `receiver` if it's a method.
+    fn call(
+        &mut self,
+        function: &Function,
+        receiver: Option<Value>,
+        args: Vec<Value>,
+        span: Span,
+    ) -> EvalResult {
+        let decl = &function.decl;
+        if args.len() != decl.params.len() {
+            return Err(EiRuntimeError::new(
+                format!(
+                    "{} expects {} argument(s) but was given {}",
+                    decl.name.lexeme,
+                    decl.params.len(),
+                    args.len()
+                ),
+                span,
+            ));
+        }
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err(EiRuntimeError::new("Maximum call depth exceeded", span));
+        }
+
+        let mut env = Environment::enclosed_by(Rc::clone(&function.closure));
+        if let Some(receiver) = receiver {
+            env.define("self", receiver, false);
+        }
+        for (param, arg) in decl.params.iter().zip(args) {
+            env.define(&param.name.lexeme, arg, false);
+        }
+
+        self.call_depth += 1;
+        let result = self.execute_block(&decl.body, env);
+        self.call_depth -= 1;
+        result
+    }
+}
+
+impl Interpreter {
+    /// Evaluates either side of a `&&` or `||`, only evaluating the right
+    /// hand side if the left doesn't decide the result.
+    fn logical(&mut self, left: &Expr, op: &Token, right: &Expr) -> EvalResult {
+        let l = self.bool_operand(left, op)?;
+        let short_circuits = match op.kind {
+            TokenKind::Or => l,
+            _ => !l,
+        };
+        if short_circuits {
+            return Ok(Value::Bool(l));
+        }
+        self.bool_operand(right, op).map(Value::Bool)
+    }
+
+    fn condition(&mut self, e: &Expr) -> Result<bool, EiRuntimeError> {
+        match e.accept(self)? {
+            Value::Bool(b) => Ok(b),
+            v => Err(EiRuntimeError::new(
+                format!("Condition must be a Bool, found {}", v.type_name()),
+                e.span(),
+            )),
+        }
+    }
+
+    fn evaluate_all(&mut self, es: &[Expr]) -> Result<Vec<Value>, EiRuntimeError> {
+        es.iter().map(|e| e.accept(self)).collect()
+    }
+
+    fn call_value(
+        &mut self,
+        callee: Value,
+        callee_span: Span,
+        args: &[Expr],
+        span: Span,
+    ) -> EvalResult {
+        match callee {
+            Value::NativeFn(native) => {
+                let arg_vals = self.evaluate_all(args)?;
+                (native.body)(&arg_vals).map_err(|message| EiRuntimeError::new(message, span))
+            }
+            Value::Fn(function) => {
+                let arg_vals = self.evaluate_all(args)?;
+                self.call(&function, None, arg_vals, span)
+            }
+            v => Err(EiRuntimeError::new(
+                format!(
+                    "Cannot call a non-function. Tried to call a value of type {}",
+                    v.type_name()
+                ),
+                callee_span,
+            )),
+        }
+    }
+
+    /// Evaluates the fields of a record literal in the order they're
+    /// written, storing them in the order they were declared.
+    fn record(
+        &mut self,
+        decl: &RecordDecl,
+        inits: &[FieldInit],
+        span: Span,
+    ) -> Result<Record, EiRuntimeError> {
+        let type_name = &decl.name.lexeme;
+        let mut values: Vec<Option<Value>> = vec![None; decl.fields.len()];
+        for init in inits {
+            let Some(i) = decl
+                .fields
+                .iter()
+                .position(|f| f.name.lexeme == init.name.lexeme)
+            else {
+                return Err(no_such_field(type_name, &init.name));
+            };
+            if values[i].is_some() {
+                return Err(EiRuntimeError::new(
+                    format!("Field {} is given more than once", init.name.lexeme),
+                    init.name.span,
+                ));
+            }
+            values[i] = Some(init.value.accept(self)?);
+        }
 
-        Interpreter { globals }
+        let missing: Vec<&str> = decl
+            .fields
+            .iter()
+            .zip(&values)
+            .filter(|(_, v)| v.is_none())
+            .map(|(f, _)| f.name.lexeme.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(EiRuntimeError::new(
+                format!("{} is missing field(s) {}", type_name, missing.join(", ")),
+                span,
+            ));
+        }
+
+        let fields = decl
+            .fields
+            .iter()
+            .zip(values)
+            .map(|(f, v)| {
+                (
+                    f.name.lexeme.clone(),
+                    v.expect("Missing fields were reported"),
+                )
+            })
+            .collect();
+        Ok(Record {
+            type_name: type_name.clone(),
+            fields,
+        })
+    }
+
+    fn bool_operand(&mut self, e: &Expr, op: &Token) -> Result<bool, EiRuntimeError> {
+        match e.accept(self)? {
+            Value::Bool(b) => Ok(b),
+            v => Err(EiRuntimeError::new(
+                format!(
+                    "Operator `{}` expects Bool operands, found {}",
+                    op.lexeme,
+                    v.type_name()
+                ),
+                e.span(),
+            )),
+        }
+    }
+}
+
+fn checked_int(result: Option<i64>, op: &Token) -> EvalResult {
+    result
+        .map(Value::Int)
+        .ok_or_else(|| EiRuntimeError::new("Integer overflow", op.span))
+}
+
+fn literal_value(l: &Literal) -> Value {
+    match l {
+        // TODO(STR_TABLE): avoid this clone
+        Literal::String(s) => Value::String(s.clone()),
+        Literal::Int(i) => Value::Int(*i),
+        Literal::Bool(b) => Value::Bool(*b),
     }
+}
 
-    fn interpret(&mut self, p: UntypedProgram) {
-        for s in p.stmts {
-            match s {
-                ast::Stmt::Expr { e } => {
-                    e.accept(self);
-                    ()
+/// Whether `value` matches `pattern`, adding any names the pattern binds to
+/// `bindings`. On failure, `bindings` may contain names bound by the part of
+/// the pattern which did match.
+fn match_pattern(
+    pattern: &Pattern,
+    value: &Value,
+    bindings: &mut Vec<(String, Value)>,
+) -> Result<bool, EiRuntimeError> {
+    match (pattern, value) {
+        (Pattern::Wildcard { .. }, _) => Ok(true),
+        (Pattern::Literal { l, .. }, v) => Ok(literal_value(l).equals(v) == Some(true)),
+        (Pattern::Identifier { name }, Value::Enum(e)) if e.has_variant(&name.lexeme) => {
+            Ok(e.variant == name.lexeme)
+        }
+        (Pattern::Identifier { name }, v) => {
+            bindings.push((name.lexeme.clone(), v.clone()));
+            Ok(true)
+        }
+        // `succ(p)` matches a positive Int when `p` matches its predecessor.
+        (
+            Pattern::Variant {
+                enum_name: None,
+                name,
+                fields: Some(fields),
+                ..
+            },
+            Value::Int(i),
+        ) if name.lexeme == "succ" && fields.len() == 1 => {
+            Ok(*i > 0 && match_pattern(&fields[0], &Value::Int(i - 1), bindings)?)
+        }
+        (
+            Pattern::Variant {
+                enum_name,
+                name,
+                fields,
+                span,
+            },
+            v,
+        ) => {
+            let Value::Enum(e) = v else {
+                return Ok(false);
+            };
+            if let Some(enum_name) = enum_name {
+                if enum_name.lexeme != e.type_name() {
+                    return Ok(false);
+                }
+                if !e.has_variant(&name.lexeme) {
+                    return Err(EiRuntimeError::new(
+                        format!("{} has no variant {}", e.type_name(), name.lexeme),
+                        name.span,
+                    ));
                 }
             }
+            if e.variant != name.lexeme {
+                return Ok(false);
+            }
+            let Some(patterns) = fields else {
+                return Ok(true);
+            };
+            if patterns.len() != e.fields.len() {
+                return Err(EiRuntimeError::new(
+                    format!(
+                        "{}.{} has {} field(s) but the pattern has {}",
+                        e.type_name(),
+                        e.variant,
+                        e.fields.len(),
+                        patterns.len()
+                    ),
+                    *span,
+                ));
+            }
+            for (p, (_, v)) in patterns.iter().zip(&e.fields) {
+                if !match_pattern(p, v, bindings)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (Pattern::Tuple { elements, .. }, Value::Tuple(values)) => {
+            if elements.len() != values.len() {
+                return Ok(false);
+            }
+            for (p, v) in elements.iter().zip(values) {
+                if !match_pattern(p, v, bindings)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (Pattern::Tuple { .. }, _) => Ok(false),
+        (Pattern::Or { alternatives }, v) => {
+            let bound = bindings.len();
+            for alternative in alternatives {
+                if match_pattern(alternative, v, bindings)? {
+                    return Ok(true);
+                }
+                bindings.truncate(bound);
+            }
+            Ok(false)
+        }
+    }
+}
+
+fn undefined(name: &Token) -> EiRuntimeError {
+    EiRuntimeError::new(format!("Undefined identifier {}", name.lexeme), name.span)
+}
+
+/// Unit variants are values in their own right, while variants with fields
+/// are constructed by calling them with a value for each field, in order.
+fn variant_value(decl: &Rc<EnumDecl>, variant: &Variant) -> Value {
+    let variant_name = variant.name.lexeme.clone();
+    if variant.fields.is_empty() {
+        return Value::Enum(Rc::new(Enum {
+            decl: Rc::clone(decl),
+            variant: variant_name,
+            fields: Vec::new(),
+        }));
+    }
+
+    let field_names: Vec<String> = variant
+        .fields
+        .iter()
+        .map(|f| f.name.lexeme.clone())
+        .collect();
+    let name = format!("{}.{}", decl.name.lexeme, variant_name);
+    let decl = Rc::clone(decl);
+    let constructor = NativeFn {
+        name: name.clone(),
+        body: Box::new(move |args| {
+            if args.len() != field_names.len() {
+                return Err(format!(
+                    "{} expects {} argument(s) but was given {}",
+                    name,
+                    field_names.len(),
+                    args.len()
+                ));
+            }
+            Ok(Value::Enum(Rc::new(Enum {
+                decl: Rc::clone(&decl),
+                variant: variant_name.clone(),
+                fields: field_names
+                    .iter()
+                    .cloned()
+                    .zip(args.iter().cloned())
+                    .collect(),
+            })))
+        }),
+    };
+    Value::NativeFn(Rc::new(constructor))
+}
+
+/// The function which runs a check, taking the value being checked as its
+/// first argument.
+fn check_function(decl: &CheckDecl) -> FnDecl {
+    let value = Param {
+        name: Token::identifier("value"),
+        ty: decl.subject.clone(),
+    };
+    FnDecl {
+        name: decl.name.clone(),
+        params: std::iter::once(value)
+            .chain(decl.params.iter().cloned())
+            .collect(),
+        return_type: Some(TypeExpr::named("Bool")),
+        body: decl.body.clone(),
+    }
+}
+
+/// Whether `s` declares an item which is hoisted to the top of its scope,
+/// rather than running where it's written.
+fn is_declaration(s: &Stmt) -> bool {
+    !matches!(s, Stmt::Expr { .. } | Stmt::Let { .. } | Stmt::Mut { .. })
+}
+
+/// The name an item declaration binds in its scope, if any.
+fn declared_name(s: &Stmt) -> Option<&str> {
+    let name = match s {
+        Stmt::Fn { decl } => &decl.name,
+        Stmt::Record { decl } => &decl.name,
+        Stmt::Enum { decl } => &decl.name,
+        Stmt::Interface { decl } => &decl.name,
+        Stmt::Check { decl } => &decl.name,
+        Stmt::Expr { .. } | Stmt::Let { .. } | Stmt::Mut { .. } | Stmt::Impl { .. } => return None,
+    };
+    Some(&name.lexeme)
+}
+
+/// Checks that an `impl` provides exactly the methods required by `interface`,
+/// with the same parameter & return types.
+fn check_conformance(decl: &ImplDecl, interface: &InterfaceDecl) -> Result<(), EiRuntimeError> {
+    let interface_name = &interface.name.lexeme;
+    for method in &decl.methods {
+        let Some(required) = interface
+            .methods
+            .iter()
+            .find(|m| m.name.lexeme == method.name.lexeme)
+        else {
+            return Err(EiRuntimeError::new(
+                format!(
+                    "{} is not a method of {}",
+                    method.name.lexeme, interface_name
+                ),
+                method.name.span,
+            ));
+        };
+        let matches = method.params.len() == required.params.len()
+            && method
+                .params
+                .iter()
+                .zip(&required.params)
+                .all(|(p, q)| p.ty.same_as(&q.ty))
+            && match (&method.return_type, &required.return_type) {
+                (Some(a), Some(b)) => a.same_as(b),
+                (a, b) => a.is_none() && b.is_none(),
+            };
+        if !matches {
+            return Err(EiRuntimeError::new(
+                format!(
+                    "{} does not match its signature in {}",
+                    method.name.lexeme, interface_name
+                ),
+                method.name.span,
+            ));
         }
     }
+
+    let missing: Vec<&str> = interface
+        .methods
+        .iter()
+        .filter(|m| !decl.methods.iter().any(|d| d.name.lexeme == m.name.lexeme))
+        .map(|m| m.name.lexeme.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(EiRuntimeError::new(
+            format!(
+                "{} is missing method(s) {} required by {}",
+                decl.type_name.lexeme,
+                missing.join(", "),
+                interface_name
+            ),
+            decl.type_name.span,
+        ));
+    }
+    Ok(())
+}
+
+fn field(object: &Value, name: &Token) -> EvalResult {
+    let field = match object {
+        Value::Record(record) => record.get(&name.lexeme).cloned(),
+        Value::EnumType(decl) => {
+            let variant = decl.variants.iter().find(|v| v.name.lexeme == name.lexeme);
+            return variant.map(|v| variant_value(decl, v)).ok_or_else(|| {
+                EiRuntimeError::new(
+                    format!("{} has no variant {}", decl.name.lexeme, name.lexeme),
+                    name.span,
+                )
+            });
+        }
+        _ => None,
+    };
+    field.ok_or_else(|| no_such_field(object.type_name(), name))
 }
 
-impl ExprVisitor<Value> for Interpreter {
-    fn visit_identifier(&mut self, name: &Token) -> Value {
-        Value::GlobalFn(name.lexeme.clone())
+fn no_such_field(type_name: &str, field: &Token) -> EiRuntimeError {
+    EiRuntimeError::new(
+        format!("{} has no field {}", type_name, field.lexeme),
+        field.span,
+    )
+}
+
+fn mismatched_operands(op: &Token, l: &Value, r: &Value) -> EiRuntimeError {
+    EiRuntimeError::new(
+        format!(
+            "Operator `{}` cannot be applied to {} and {}",
+            op.lexeme,
+            l.type_name(),
+            r.type_name()
+        ),
+        op.span,
+    )
+}
+
+impl ExprVisitor<EvalResult> for Interpreter {
+    fn visit_identifier(&mut self, name: &Token) -> EvalResult {
+        self.environment
+            .borrow()
+            .get(&name.lexeme)
+            .ok_or_else(|| undefined(name))
+    }
+
+    fn visit_literal(&mut self, l: &Literal) -> EvalResult {
+        Ok(literal_value(l))
     }
 
-    fn visit_literal(&mut self, l: &Literal) -> Value {
-        match l {
-            // TODO(STR_TABLE): avoid this clone
-            Literal::String(s) => Value::String(s.clone()),
+    fn visit_function_application(
+        &mut self,
+        callee: &Expr,
+        args: &[Expr],
+        span: Span,
+    ) -> EvalResult {
+        let func = callee.accept(self)?;
+        self.call_value(func, callee.span(), args, span)
+    }
+
+    fn visit_unary(&mut self, op: &Token, right: &Expr) -> EvalResult {
+        match (&op.kind, right.accept(self)?) {
+            (TokenKind::Minus, Value::Int(i)) => checked_int(i.checked_neg(), op),
+            (TokenKind::Bang, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (_, v) => Err(EiRuntimeError::new(
+                format!(
+                    "Operator `{}` cannot be applied to {}",
+                    op.lexeme,
+                    v.type_name()
+                ),
+                op.span,
+            )),
+        }
+    }
+
+    fn visit_binary(&mut self, left: &Expr, op: &Token, right: &Expr) -> EvalResult {
+        use TokenKind::*;
+
+        if let And | Or = op.kind {
+            return self.logical(left, op, right);
         }
+
+        let l = left.accept(self)?;
+        let r = right.accept(self)?;
+        match (&op.kind, l, r) {
+            (Plus, Value::Int(a), Value::Int(b)) => checked_int(a.checked_add(b), op),
+            (Plus, Value::String(a), Value::String(b)) => Ok(Value::String(a + &b)),
+            (Minus, Value::Int(a), Value::Int(b)) => checked_int(a.checked_sub(b), op),
+            (Star, Value::Int(a), Value::Int(b)) => checked_int(a.checked_mul(b), op),
+            (Slash | Percent, Value::Int(_), Value::Int(0)) => {
+                Err(EiRuntimeError::new("Division by zero", op.span))
+            }
+            (Slash, Value::Int(a), Value::Int(b)) => checked_int(a.checked_div(b), op),
+            (Percent, Value::Int(a), Value::Int(b)) => checked_int(a.checked_rem(b), op),
+            (Less, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a < b)),
+            (LessEqual, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a <= b)),
+            (Greater, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a > b)),
+            (GreaterEqual, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a >= b)),
+            (EqualEqual | BangEqual, l, r) => match l.equals(&r) {
+                Some(equal) => Ok(Value::Bool(equal == (op.kind == EqualEqual))),
+                None => Err(mismatched_operands(op, &l, &r)),
+            },
+            (_, l, r) => Err(mismatched_operands(op, &l, &r)),
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block) -> EvalResult {
+        let env = Environment::enclosed_by(Rc::clone(&self.environment));
+        self.execute_block(block, env)
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Block,
+        else_branch: Option<&Expr>,
+    ) -> EvalResult {
+        if self.condition(condition)? {
+            self.visit_block(then_branch)
+        } else {
+            match else_branch {
+                Some(e) => e.accept(self),
+                None => Ok(Value::Void),
+            }
+        }
+    }
+
+    fn visit_while(&mut self, condition: &Expr, body: &Block) -> EvalResult {
+        while self.condition(condition)? {
+            self.visit_block(body)?;
+        }
+        Ok(Value::Void)
+    }
+
+    fn visit_record_literal(
+        &mut self,
+        name: &Token,
+        fields: &[FieldInit],
+        span: Span,
+    ) -> EvalResult {
+        match self.visit_identifier(name)? {
+            Value::RecordType(decl) => {
+                Ok(Value::Record(Rc::new(self.record(&decl, fields, span)?)))
+            }
+            v => Err(EiRuntimeError::new(
+                format!(
+                    "{} is not a record type, found {}",
+                    name.lexeme,
+                    v.type_name()
+                ),
+                name.span,
+            )),
+        }
+    }
+
+    fn visit_field_access(&mut self, object: &Expr, name: &Token) -> EvalResult {
+        field(&object.accept(self)?, name)
+    }
+
+    fn visit_method_call(
+        &mut self,
+        receiver: &Expr,
+        method: &Token,
+        args: &[Expr],
+        span: Span,
+    ) -> EvalResult {
+        let receiver_value = receiver.accept(self)?;
+        let declared = self
+            .methods
+            .get(receiver_value.type_name())
+            .and_then(|methods| methods.get(&method.lexeme))
+            .cloned();
+        if let Some(function) = declared {
+            let arg_vals = self.evaluate_all(args)?;
+            return self.call(&function, Some(receiver_value), arg_vals, span);
+        }
+        if builtins::has_native_method(&receiver_value, &method.lexeme) {
+            let arg_vals = self.evaluate_all(args)?;
+            return builtins::call_native_method(&receiver_value, &method.lexeme, &arg_vals)
+                .map_err(|message| EiRuntimeError::new(message, span));
+        }
+
+        // Otherwise this calls a field, e.g. `CoffeeMaker.aeropress(true)`.
+        match (&receiver_value, field(&receiver_value, method)) {
+            (_, Ok(callee)) => self.call_value(callee, receiver.span().to(method.span), args, span),
+            (Value::EnumType(_), Err(e)) => Err(e),
+            (v, Err(_)) => Err(EiRuntimeError::new(
+                format!("{} has no method {}", v.type_name(), method.lexeme),
+                method.span,
+            )),
+        }
+    }
+
+    fn visit_tuple(&mut self, elements: &[Expr]) -> EvalResult {
+        self.evaluate_all(elements).map(Value::Tuple)
     }
 
-    fn visit_function_application(&mut self, callee: &Expr, args: &[Expr]) -> Value {
-        let func = callee.accept(self);
-        match func {
-            Value::GlobalFn(name) => {
-                let arg_vals: Vec<Value> = args.iter().map(|e| e.accept(self)).collect();
-                self.globals.call(&name, &arg_vals)
+    fn visit_match(&mut self, scrutinee: &Expr, arms: &[MatchArm], _span: Span) -> EvalResult {
+        let value = scrutinee.accept(self)?;
+        for arm in arms {
+            let mut bindings = Vec::new();
+            if match_pattern(&arm.pattern, &value, &mut bindings)? {
+                let mut env = Environment::enclosed_by(Rc::clone(&self.environment));
+                for (name, v) in bindings {
+                    env.define(&name, v, false);
+                }
+                return self.with_environment(env, |i| arm.body.accept(i));
             }
-            v => panic!("Cannot call a non-function. Tried to call: {:?}", v),
+        }
+        Err(EiRuntimeError::new(
+            format!("No match arm matches {}", value),
+            scrutinee.span(),
+        ))
+    }
+
+    fn visit_assign(&mut self, name: &Token, value: &Expr) -> EvalResult {
+        let value = value.accept(self)?;
+        match self.environment.borrow_mut().assign(&name.lexeme, value) {
+            Ok(()) => Ok(Value::Void),
+            Err(AssignError::Undefined) => Err(undefined(name)),
+            Err(AssignError::Immutable) => Err(EiRuntimeError::new(
+                format!("Cannot assign to immutable binding {}", name.lexeme),
+                name.span,
+            )),
         }
     }
 }
@@ -108,6 +1153,8 @@ mod tests {
 
     use super::*;
     use ast::{Expr, Stmt};
+    use lexer::token_stream;
+    use parser::parse;
     use test_with_parameters::*;
 
     struct FakeWrite {
@@ -143,7 +1190,7 @@ mod tests {
         let mut interpreter = Interpreter::new(write);
 
         let empty_program = UntypedProgram::empty();
-        interpreter.interpret(empty_program);
+        interpreter.interpret(empty_program).unwrap();
 
         assert!(our_write.borrow().written.is_empty())
     }
@@ -160,6 +1207,36 @@ mod tests {
             e: Expr::FunctionApplication {
                 callee: Box::new(Expr::identifier("print_ln")),
                 args: vec![input.into()],
+                span: Span::default(),
+            },
+        }]);
+
+        let write = Rc::new(RefCell::new(FakeWrite::new()));
+        let our_write = Rc::clone(&write);
+
+        let mut interpreter = Interpreter::new(write);
+
+        interpreter.interpret(program).unwrap();
+
+        let written = our_write.borrow().written.clone();
+        assert_eq!(written, output)
+    }
+
+    #[test_with_parameters(
+        [ literal_to_print, expected_output    ]
+        [ 0.into()        , &["0", "\n"]       ]
+        [ 500.into()      , &["500", "\n"]     ]
+        [ true.into()     , &["true", "\n"]    ]
+        [ false.into()    , &["false", "\n"]   ]
+    )]
+    fn println_outputs_literal_arg(literal_to_print: Literal, expected_output: &[&str]) {
+        let output = expected_output.to_owned();
+
+        let program = UntypedProgram::with_stmts(vec![Stmt::Expr {
+            e: Expr::FunctionApplication {
+                callee: Box::new(Expr::identifier("print_ln")),
+                args: vec![literal_to_print.into()],
+                span: Span::default(),
             },
         }]);
 
@@ -168,9 +1245,455 @@ mod tests {
 
         let mut interpreter = Interpreter::new(write);
 
-        interpreter.interpret(program);
+        interpreter.interpret(program).unwrap();
 
         let written = our_write.borrow().written.clone();
         assert_eq!(written, output)
     }
+
+    #[test_with_parameters(
+        [ callee    , args                     , message                                                   ]
+        [ "nope"    , vec![]                   , "Undefined identifier nope"                               ]
+        [ "print_ln", vec![]                   , "Incorrect arguments for print_ln: []"                    ]
+        [ "print_ln", vec!["a".into(), "b".into()], "Incorrect arguments for print_ln: [String(\"a\"), String(\"b\")]" ]
+    )]
+    fn bad_calls_are_runtime_errors(callee: &str, args: Vec<Expr>, message: &str) {
+        let program = UntypedProgram::with_stmts(vec![Stmt::Expr {
+            e: Expr::FunctionApplication {
+                callee: Box::new(Expr::identifier(callee)),
+                args,
+                span: Span::default(),
+            },
+        }]);
+
+        let mut interpreter = Interpreter::new(Rc::new(RefCell::new(FakeWrite::new())));
+
+        let err = interpreter.interpret(program).unwrap_err();
+        assert_eq!(message, err.message);
+    }
+
+    #[test]
+    fn calling_a_non_function_is_a_runtime_error() {
+        let span = Span::new(0, 3, 1, 1);
+        let program = UntypedProgram::with_stmts(vec![Stmt::Expr {
+            e: Expr::FunctionApplication {
+                callee: Box::new(Expr::Literal {
+                    l: Literal::new("a"),
+                    span,
+                }),
+                args: vec![],
+                span: Span::default(),
+            },
+        }]);
+
+        let mut interpreter = Interpreter::new(Rc::new(RefCell::new(FakeWrite::new())));
+
+        let err = interpreter.interpret(program).unwrap_err();
+        assert_eq!(
+            "Cannot call a non-function. Tried to call a value of type String",
+            err.message
+        );
+        assert_eq!((1, 1), (err.span.line, err.span.column));
+    }
+
+    #[test_with_parameters(
+        [ expression                   , expected_output ]
+        [ "1 + 2 * 3"                  , "7"             ]
+        [ "(1 + 2) * 3"                , "9"             ]
+        [ "7 / 2 - 7 % 2"              , "2"             ]
+        [ "-(2 - 5)"                   , "3"             ]
+        [ "\"ab\" + \"cd\""            , "abcd"          ]
+        [ "1 < 2 && 2 <= 2"            , "true"          ]
+        [ "3 > 4 || 4 >= 5"            , "false"         ]
+        [ "!(1 == 1) != (\"a\" == \"b\")", "false"         ]
+        [ "true == !false"             , "true"          ]
+    )]
+    fn evaluates_operators(expression: &str, expected_output: &str) {
+        let (result, written) = util::run(&format!("print_ln({});", expression));
+        result.unwrap();
+        assert_eq!(format!("{}\n", expected_output), written)
+    }
+
+    #[test_with_parameters(
+        [ src                                , message                                           ]
+        [ "false && print_ln(1);"            , ""                                                ]
+        [ "true || print_ln(1);"             , ""                                                ]
+        [ "1 && true;"                       , "Operator `&&` expects Bool operands, found Int"  ]
+        [ "true && \"a\";"                   , "Operator `&&` expects Bool operands, found String" ]
+        [ "-true;"                           , "Operator `-` cannot be applied to Bool"          ]
+        [ "1 + \"a\";"                       , "Operator `+` cannot be applied to Int and String" ]
+        [ "1 == true;"                       , "Operator `==` cannot be applied to Int and Bool" ]
+        [ "1 / 0;"                           , "Division by zero"                                ]
+        [ "1 % 0;"                           , "Division by zero"                                ]
+        [ "9223372036854775807 + 1;"         , "Integer overflow"                                ]
+        [ "-9223372036854775807 - 2;"        , "Integer overflow"                                ]
+    )]
+    fn operator_errors_are_runtime_errors(src: &str, message: &str) {
+        let (result, written) = util::run(src);
+        assert_eq!("", written);
+        assert_eq!(message, result.err().map(|e| e.message).unwrap_or_default());
+    }
+
+    #[test_with_parameters(
+        [ src                                                  , expected_output ]
+        [ "let x = 1; print_ln(x);"                            , "1\n"           ]
+        [ "let x: Int = 1; let y = x + 1; print_ln(y);"        , "2\n"           ]
+        [ "mut x = \"a\"; x = x + \"b\"; print_ln(x);"         , "ab\n"          ]
+        [ "mut x = 1; mut y = 2; x = y = 3; print_ln(y);"      , "3\n"           ]
+        [ "let x = 1; let x = x + 1; print_ln(x);"             , "2\n"           ]
+        [ "let p = print_ln; p(\"hi\");"                       , "hi\n"          ]
+    )]
+    fn bindings_can_be_used(src: &str, expected_output: &str) {
+        let (result, written) = util::run(src);
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test_with_parameters(
+        [ src                      , message                                  , column ]
+        [ "let x = 1; x = 2;"      , "Cannot assign to immutable binding x"   , 12     ]
+        [ "print_ln = 2;"          , "Cannot assign to immutable binding print_ln", 1  ]
+        [ "y = 2;"                 , "Undefined identifier y"                 , 1      ]
+        [ "let x = 1; print_ln(y);", "Undefined identifier y"                 , 21     ]
+    )]
+    fn binding_errors_are_runtime_errors(src: &str, message: &str, column: usize) {
+        let (result, _) = util::run(src);
+        let err = result.unwrap_err();
+        assert_eq!(message, err.message);
+        assert_eq!(column, err.span.column);
+    }
+
+    #[test_with_parameters(
+        [ src                                                                 , expected_output ]
+        [ include_str!("../../../docs/examples/hello_fn.ei")                  , "Hello, Ei\n"    ]
+        [ "fn add(x: Int, y: Int) -> Int { x + y } print_ln(add(1, 2));"      , "3\n"           ]
+        [ "fn greet() { print_ln(\"hi\"); } greet(); greet();"                 , "hi\nhi\n"       ]
+        [ "fn f() -> Int { let x = 1; mut y = x; y = y + 1; y } print_ln(f());", "2\n"           ]
+        [ "fn a() -> Int { b() } fn b() -> Int { 1 } print_ln(a());"          , "1\n"           ]
+        [ "let x = 1; fn get() -> Int { x } let x = 2; print_ln(get());"      , "1\n"           ]
+        [ "let x = 1; let x = \"s\"; fn get() -> String { x } print_ln(get());", "s\n"           ]
+        [ "fn f(x: Int) -> Int { fn g() -> Int { x } let x = \"s\"; g() } print_ln(f(7));", "7\n" ]
+        [ "mut x = 1; fn bump() { x = x + 1; } let y = 0; let y = 1; bump(); print_ln(x + y);", "3\n" ]
+        [ "print_ln(\"started\"); let x = 1; fn get() -> Int { x + 1 } let x = \"s\"; print_ln(get());",
+          "started\n2\n" ]
+        [ "print_ln(\"started\"); let x = 1; fn get() -> Int { x + 1 } let x = \"s\";
+           let y: Int = get(); print_ln(y + 1);"                               , "started\n3\n"   ]
+        [ "fn outer() -> Int { fn inner() -> Int { 3 } inner() } print_ln(outer());", "3\n"     ]
+        [ "print_ln(later()); fn later() -> Int { 4 }"                        , "4\n"           ]
+        [ "impl P { fn a() -> Int { self.a } } record P { a: Int } print_ln(P { a: 5 }.a());", "5\n" ]
+        [ "fn f(x: Int) -> Int { x } let x = 10; print_ln(f(1) + x);"         , "11\n"          ]
+    )]
+    fn user_defined_functions(src: &str, expected_output: &str) {
+        let (result, written) = util::run(src);
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test_with_parameters(
+        [ src                                                , message                                         ]
+        [ "fn f(a: Int) {} f();"                             , "f expects 1 argument(s) but was given 0"       ]
+        [ "fn f() {} f(1, 2);"                               , "f expects 0 argument(s) but was given 2"       ]
+        [ "fn f(a: Int) { a = 2; } f(1);"                    , "Cannot assign to immutable binding a"          ]
+        [ "fn f() { let y = 1; } f(); print_ln(y);"          , "Undefined identifier y"                        ]
+        [ "fn f() -> Int { f() } f();"                       , "Maximum call depth exceeded"                   ]
+    )]
+    fn function_errors_are_runtime_errors(src: &str, message: &str) {
+        let (result, _) = util::run(src);
+        assert_eq!(message, result.unwrap_err().message);
+    }
+
+    #[test_with_parameters(
+        [ src                                                           , expected_output ]
+        [ "print_ln({ 1 });"                                            , "1\n"           ]
+        [ "print_ln({ let x = 2; x * 3 } + 1);"                         , "7\n"           ]
+        [ "print_ln({ print_ln(1); });"                                 , "1\n()\n"       ]
+        [ "let x = 1; { let x = 2; print_ln(x); } print_ln(x);"         , "2\n1\n"        ]
+        [ "mut x = 1; { x = 2; } print_ln(x);"                          , "2\n"           ]
+        [ "fn f() -> Int { let a = { 1 }; { a + 1 } } print_ln(f());"   , "2\n"           ]
+    )]
+    fn blocks_evaluate_in_child_scope(src: &str, expected_output: &str) {
+        let (result, written) = util::run(src);
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test]
+    fn block_bindings_are_not_visible_outside() {
+        let (result, _) = util::run("{ let y = 1; } print_ln(y);");
+        assert_eq!("Undefined identifier y", result.unwrap_err().message);
+    }
+
+    #[test_with_parameters(
+        [ src                                                                     , expected_output ]
+        [ "print_ln(if true { 1 } else { 2 });"                                   , "1\n"           ]
+        [ "print_ln(if 1 > 2 { 1 } else { 2 });"                                  , "2\n"           ]
+        [ "print_ln(if false { 1 });"                                             , "()\n"          ]
+        [ "let x = 5; if x < 3 { print_ln(\"a\"); } else if x < 6 { print_ln(\"b\"); } else { print_ln(\"c\"); }", "b\n" ]
+        [ "mut i = 0; while i < 3 { print_ln(i); i = i + 1; }"                    , "0\n1\n2\n"     ]
+        [ "mut i = 0; while false { i = 1; } print_ln(i);"                        , "0\n"           ]
+        [ "fn fizz_buzz(i: Int) -> String {
+               mut result = \"\";
+               if i % 3 == 0 { result = result + \"Fizz\"; }
+               if i % 5 == 0 { result = result + \"Buzz\"; }
+               if result == \"\" { \"-\" } else { result }
+           }
+           mut i = 1;
+           while i <= 15 { print_ln(fizz_buzz(i)); i = i + 1; }"                 , "-\n-\nFizz\n-\nBuzz\nFizz\n-\n-\nFizz\nBuzz\n-\nFizz\n-\n-\nFizzBuzz\n" ]
+        [ "fn fib(n: Int) -> Int { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } } print_ln(fib(10));", "55\n" ]
+    )]
+    fn conditionals_and_loops(src: &str, expected_output: &str) {
+        let (result, written) = util::run(src);
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test_with_parameters(
+        [ src                          , message                                , column ]
+        [ "if 1 { }"                   , "Condition must be a Bool, found Int"  , 4      ]
+        [ "while \"a\" { }"            , "Condition must be a Bool, found String", 7     ]
+        [ "if false {} else if 0 {}"   , "Condition must be a Bool, found Int"  , 21     ]
+    )]
+    fn non_bool_conditions_are_runtime_errors(src: &str, message: &str, column: usize) {
+        let (result, _) = util::run(src);
+        let err = result.unwrap_err();
+        assert_eq!(message, err.message);
+        assert_eq!(column, err.span.column);
+    }
+
+    #[test_with_parameters(
+        [ src                                                                 , expected_output ]
+        [ "record Person { name: String, age: Int }
+           let p = Person { name: \"Matthew\", age: 29 };
+           print_ln(p.name); print_ln(p.age);"                                , "Matthew\n29\n" ]
+        [ "record P { a: Int, b: Int } print_ln(P { b: 2, a: 1 });"           , "P { a: 1, b: 2 }\n" ]
+        [ "record P { s: String } print_ln(P { s: \"x\" });"                  , "P { s: \"x\" }\n" ]
+        [ "record A { x: Int } record B { a: A } print_ln(B { a: A { x: 1 } }.a.x);", "1\n"    ]
+        [ "record P { a: Int } print_ln(P { a: 1 } == P { a: 1 });"           , "true\n"        ]
+        [ "record P { a: Int } print_ln(P { a: 1 } != P { a: 2 });"           , "true\n"        ]
+        [ "record P { a: Int } let p = P { a: 1 }; if p.a == 1 { print_ln(p.a); }", "1\n"       ]
+        [ "record E {} print_ln(E {});"                                       , "E {}\n"      ]
+    )]
+    fn records(src: &str, expected_output: &str) {
+        let (result, written) = util::run(src);
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test_with_parameters(
+        [ src                                                        , message                                     , column ]
+        [ "record P { a: Int, b: Int } P { a: 1 };"                  , "P is missing field(s) b"                   , 29     ]
+        [ "record P { a: Int } P { a: 1, c: 2 };"                    , "P has no field c"                          , 31     ]
+        [ "record P { a: Int } P { a: 1, a: 2 };"                    , "Field a is given more than once"           , 31     ]
+        [ "record P { a: Int } P { a: 1 }.b;"                        , "P has no field b"                          , 32     ]
+        [ "let x = 1; x.a;"                                          , "Int has no field a"                        , 14     ]
+        [ "Q { a: 1 };"                                              , "Undefined identifier Q"                    , 1      ]
+        [ "let Q = 1; Q { a: 1 };"                                   , "Q is not a record type, found Int"         , 12     ]
+        [ "record A { x: Int } record B { y: Int } A { x: 1 } == B { y: 1 };", "Operator `==` cannot be applied to A and B", 52 ]
+    )]
+    fn record_errors_are_runtime_errors(src: &str, message: &str, column: usize) {
+        let (result, _) = util::run(src);
+        let err = result.unwrap_err();
+        assert_eq!(message, err.message);
+        assert_eq!(column, err.span.column);
+    }
+
+    #[test_with_parameters(
+        [ src                                                                       , expected_output ]
+        [ "print_ln(CoffeeMaker.espresso);"                                         , "CoffeeMaker.espresso\n" ]
+        [ "print_ln(CoffeeMaker.aeropress(true));"                                  , "CoffeeMaker.aeropress(inverted: true)\n" ]
+        [ "print_ln(CoffeeMaker.pour_over(\"v60\", 2));"                            , "CoffeeMaker.pour_over(brand: \"v60\", cups: 2)\n" ]
+        [ "let make = CoffeeMaker.aeropress; print_ln(make(false));"                , "CoffeeMaker.aeropress(inverted: false)\n" ]
+        [ "print_ln(CoffeeMaker.espresso == CoffeeMaker.espresso);"                 , "true\n"        ]
+        [ "print_ln(CoffeeMaker.espresso == CoffeeMaker.aeropress(true));"          , "false\n"       ]
+        [ "print_ln(CoffeeMaker.aeropress(true) != CoffeeMaker.aeropress(false));"  , "true\n"        ]
+        [ "record Cafe { maker: CoffeeMaker } print_ln(Cafe { maker: CoffeeMaker.espresso });", "Cafe { maker: CoffeeMaker.espresso }\n" ]
+    )]
+    fn enums(src: &str, expected_output: &str) {
+        let (result, written) = util::run(&format!("{}\n{}", util::COFFEE_MAKER, src));
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test_with_parameters(
+        [ src                                                       , message                                                 ]
+        [ "CoffeeMaker.kalita_wave;"                                , "CoffeeMaker has no variant kalita_wave"                ]
+        [ "CoffeeMaker.aeropress();"                                , "CoffeeMaker.aeropress expects 1 argument(s) but was given 0" ]
+        [ "CoffeeMaker.espresso(1);"                                , "Cannot call a non-function. Tried to call a value of type CoffeeMaker" ]
+        [ "enum Other { espresso } CoffeeMaker.espresso == Other.espresso;", "Operator `==` cannot be applied to CoffeeMaker and Other" ]
+    )]
+    fn enum_errors_are_runtime_errors(src: &str, message: &str) {
+        let (result, _) = util::run(&format!("{}\n{}", util::COFFEE_MAKER, src));
+        assert_eq!(message, result.unwrap_err().message);
+    }
+
+    #[test_with_parameters(
+        [ src                                                                         , expected_output ]
+        [ "fn is_pour_over(m: CoffeeMaker) -> Bool {
+               match m { pour_over | CoffeeMaker.aeropress(true) => true, _ => false }
+           }
+           print_ln(is_pour_over(CoffeeMaker.espresso));
+           print_ln(is_pour_over(CoffeeMaker.aeropress(true)));
+           print_ln(is_pour_over(CoffeeMaker.aeropress(false)));
+           print_ln(is_pour_over(CoffeeMaker.pour_over(\"v60\", 1)));"                 , "false\ntrue\nfalse\ntrue\n" ]
+        [ "print_ln(match CoffeeMaker.pour_over(\"v60\", 2) {
+               pour_over(brand, 1) => brand,
+               pour_over(brand, cups) => brand + \" x\" + match cups { 2 => \"2\", _ => \"?\" },
+               _ => \"none\",
+           });"                                                                        , "v60 x2\n"     ]
+        [ "print_ln(match (1, \"a\") { 0, _ => \"zero\", n, \"a\" => \"a\", _ => \"other\" });", "a\n"      ]
+        [ "print_ln(match -3 { -3 => \"minus three\", _ => \"other\" });"             , "minus three\n" ]
+        [ "print_ln(match 5 { x => x + 1 });"                                          , "6\n"           ]
+        [ "print_ln(match (3, 0) { (succ(succ(n)), _) => n, (_, succ(_)) => -1, _ => 0 });", "1\n"          ]
+        [ "print_ln(match 0 { succ(_) => \"positive\", _ => \"not positive\" });"       , "not positive\n" ]
+        [ "let x = 1; match 2 { x => {} } print_ln(x);"                                , "1\n"           ]
+        [ "match true { true => { print_ln(1); } false => { print_ln(2); } }"          , "1\n"           ]
+        [ "print_ln(match (1, (2, 3)) { (a, (b, c)) => a + b + c });"                  , "6\n"           ]
+        [ "print_ln((1, \"a\", (true, CoffeeMaker.espresso)));"                        , "(1, \"a\", (true, CoffeeMaker.espresso))\n" ]
+        [ "print_ln((1, 2) == (1, 2));"                                                , "true\n"        ]
+    )]
+    fn match_expressions(src: &str, expected_output: &str) {
+        let (result, written) = util::run(&format!("{}\n{}", util::COFFEE_MAKER, src));
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test_with_parameters(
+        [ src                                                           , message                                                    ]
+        [ "match 3 { 1 => 1, 2 => 2 };"                                 , "No match arm matches 3"                                   ]
+        [ "match CoffeeMaker.espresso { CoffeeMaker.drip => 1 };"       , "CoffeeMaker has no variant drip"                          ]
+        [ "match CoffeeMaker.aeropress(true) { aeropress(a, b) => 1 };" , "CoffeeMaker.aeropress has 1 field(s) but the pattern has 2" ]
+        [ "match 1 { x => x }; x;"                                      , "Undefined identifier x"                                   ]
+    )]
+    fn match_errors_are_runtime_errors(src: &str, message: &str) {
+        let (result, _) = util::run(&format!("{}\n{}", util::COFFEE_MAKER, src));
+        assert_eq!(message, result.unwrap_err().message);
+    }
+
+    #[test_with_parameters(
+        [ src                                                                         , expected_output ]
+        [ "record Person { name: String, age: Int }
+           impl Person {
+               fn is_adult() -> Bool { self.age > 18 }
+               fn greeting(other: Person) -> String {
+                   \"Hi \".appending(other.name).appending(\", I'm \").appending(self.name)
+               }
+           }
+           let me = Person { name: \"Matthew\", age: 29 };
+           print_ln(me.is_adult());
+           print_ln(me.greeting(Person { name: \"Ei\", age: 1 }));"                   , "true\nHi Ei, I'm Matthew\n" ]
+        [ "impl CoffeeMaker {
+               fn is_pour_over() -> Bool { match self { pour_over => true, _ => false } }
+               fn describe() -> String { if self.is_pour_over() { \"slow\" } else { \"fast\" } }
+           }
+           print_ln(CoffeeMaker.espresso.describe());
+           print_ln(CoffeeMaker.pour_over(\"v60\", 1).describe());"                   , "fast\nslow\n" ]
+        [ "impl Int { fn double() -> Int { self * 2 } } print_ln(21.double());"         , "42\n"          ]
+        [ "print_ln(\"\".is_empty()); print_ln(\"ab\".is_empty());"                    , "true\nfalse\n" ]
+        [ "print_ln(\"héllo\".length());"                                              , "5\n"           ]
+        [ "print_ln((1 + 2).to_string().appending(true.to_string()));"                  , "3true\n"       ]
+        [ "record R { f: Int } impl R { fn f() -> Int { 2 } } let r = R { f: 1 }; print_ln(r.f); print_ln(r.f());", "1\n2\n" ]
+    )]
+    fn methods(src: &str, expected_output: &str) {
+        let (result, written) = util::run(&format!("{}\n{}", util::COFFEE_MAKER, src));
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test_with_parameters(
+        [ src                                                  , message                                               ]
+        [ "1.nope();"                                          , "Int has no method nope"                              ]
+        [ "\"a\".appending(1);"                                , "String.appending expects a String argument, found Int" ]
+        [ "\"a\".is_empty(1);"                                 , "String.is_empty expects 0 argument(s) but was given 1" ]
+        [ "impl Nope { fn f() {} }"                            , "Cannot implement methods for unknown type Nope"      ]
+        [ "let x = 1; impl x { fn f() {} }"                    , "Cannot implement methods for unknown type x"         ]
+        [ "impl Int { fn f(a: Int) {} } 1.f();"                , "f expects 1 argument(s) but was given 0"             ]
+        [ "impl Int { fn f() -> Int { self } } self;"          , "Undefined identifier self"                           ]
+        [ "CoffeeMaker.drip(1);"                               , "CoffeeMaker has no variant drip"                     ]
+    )]
+    fn method_errors_are_runtime_errors(src: &str, message: &str) {
+        let (result, _) = util::run(&format!("{}\n{}", util::COFFEE_MAKER, src));
+        assert_eq!(message, result.unwrap_err().message);
+    }
+
+    #[test_with_parameters(
+        [ src                                                                          , expected_output ]
+        [ "record Person { name: String }
+           impl Person: Describable { fn description() -> String { self.name } }
+           impl CoffeeMaker: Describable {
+               fn description() -> String {
+                   match self { espresso => \"espresso\", _ => \"other\" }
+               }
+           }
+           fn describe(d: Describable) -> String { \"It's \".appending(d.description()) }
+           print_ln(describe(Person { name: \"Matthew\" }));
+           print_ln(describe(CoffeeMaker.espresso));"                                  , "It's Matthew\nIt's espresso\n" ]
+        [ "impl Int: Describable { fn description() -> String { self.to_string() } }
+           impl Int { fn double() -> Int { self * 2 } }
+           print_ln(2.double().description());"                                        , "4\n"            ]
+        [ "interface Empty {} impl Bool: Empty {} print_ln(Empty);"                    , "<interface Empty>\n" ]
+    )]
+    fn interfaces(src: &str, expected_output: &str) {
+        let (result, written) = util::run(&format!(
+            "{}\ninterface Describable {{ fn description() -> String }}\n{}",
+            util::COFFEE_MAKER,
+            src
+        ));
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    #[test_with_parameters(
+        [ src                                                                 , message                                                  ]
+        [ "impl Int: Describable {}"                                          , "Int is missing method(s) description, name required by Describable" ]
+        [ "impl Int: Describable { fn description() -> String { \"\" } }"     , "Int is missing method(s) name required by Describable"   ]
+        [ "impl Int: Describable { fn description() -> Int { 1 } fn name() {} }", "description does not match its signature in Describable" ]
+        [ "impl Int: Describable { fn name(x: Int) {} }"                      , "name does not match its signature in Describable"        ]
+        [ "impl Int: Describable { fn other() {} }"                           , "other is not a method of Describable"                    ]
+        [ "impl Int: Nope {}"                                                 , "Unknown interface Nope"                                  ]
+        [ "impl Int: Int {}"                                                  , "Unknown interface Int"                                   ]
+    )]
+    fn interface_errors_are_runtime_errors(src: &str, message: &str) {
+        let (result, _) = util::run(&format!(
+            "interface Describable {{ fn description() -> String fn name() }}\n{}",
+            src
+        ));
+        assert_eq!(message, result.unwrap_err().message);
+    }
+
+    #[test_with_parameters(
+        [ src                                                , expected_output        ]
+        [ include_str!("../../../docs/examples/hello_world.ei"), "Hello, World\n"     ]
+        [ include_str!("../../../docs/examples/hello_fn.ei")   , "Hello, Ei\n"        ]
+        [ include_str!("../../../docs/examples/refinements.ei"), "Matthew\n"          ]
+        [ include_str!("../../../docs/examples/divide.ei")     , "5\n0\ntrue\n"       ]
+    )]
+    fn runs_examples(src: &str, expected_output: &str) {
+        let (result, written) = util::run(src);
+        result.unwrap();
+        assert_eq!(expected_output, written)
+    }
+
+    mod util {
+        use super::*;
+
+        pub(crate) const COFFEE_MAKER: &str = "enum CoffeeMaker {
+            aeropress(inverted: Bool),
+            espresso,
+            pour_over(brand: String, cups: Int),
+        }";
+
+        /// Parses & runs `src`, returning the result alongside anything
+        /// which was written to the output.
+        pub(crate) fn run(src: &str) -> (Result<(), EiRuntimeError>, String) {
+            let program = parse(token_stream(src)).expect("Test program should parse");
+
+            let write = Rc::new(RefCell::new(FakeWrite::new()));
+            let our_write = Rc::clone(&write);
+
+            let result = Interpreter::new(write).interpret(program);
+
+            let written = our_write.borrow().written.concat();
+            (result, written)
+        }
+    }
 }
@@ -11,7 +11,13 @@ static KEYWORDS: phf::Map<&str, TokenKind> = phf_map! {
     "interface" => TokenKind::Interface,
     "impl"      => TokenKind::Impl,
     "check"     => TokenKind::Check,
+    "checking"  => TokenKind::Checking,
     "match"     => TokenKind::Match,
+    "true"      => TokenKind::True,
+    "false"     => TokenKind::False,
+    "if"        => TokenKind::If,
+    "else"      => TokenKind::Else,
+    "while"     => TokenKind::While,
 };
 
 pub fn token_stream<'src>(src: &'src str) -> TokenStream<'src> {
@@ -20,14 +26,24 @@ pub fn token_stream<'src>(src: &'src str) -> TokenStream<'src> {
         src: src.chars().peekable(),
         current_token_size: 0,
         current_token_start: 0,
+        current_token_line: 1,
+        current_token_column: 1,
+        line: 1,
+        column: 1,
     }
 }
 
 pub struct TokenStream<'src> {
     raw: &'src str,
     src: Peekable<Chars<'src>>,
+    // Both measured in bytes, so that they can be used to slice `raw`.
     current_token_size: usize,
     current_token_start: usize,
+    current_token_line: usize,
+    current_token_column: usize,
+    // The position of the next unread char.
+    line: usize,
+    column: usize,
 }
 
 impl<'src> Iterator for TokenStream<'src> {
@@ -37,17 +53,17 @@ impl<'src> Iterator for TokenStream<'src> {
         self.next_token_kind().map(|kind| Token {
             kind,
             lexeme: self.lexeme().to_string(),
+            span: self.span(),
         })
     }
 }
 
 impl<'src> TokenStream<'src> {
     fn next_token_kind(&mut self) -> Option<TokenKind> {
-        self.prepare_for_next_token();
         self.skip_whitespace();
+        self.prepare_for_next_token();
 
-        let next = self.src.next();
-        self.current_token_size += 1;
+        let next = self.advance();
 
         next.map(|nxt| {
             use TokenKind::*;
@@ -68,7 +84,9 @@ impl<'src> TokenStream<'src> {
                 '-' => Minus,
                 '/' => Slash,
                 '*' => Star,
+                '%' => Percent,
                 '=' if self.consume('=') => EqualEqual,
+                '=' if self.consume('>') => FatArrow,
                 '=' => Equal,
                 '>' if self.consume('=') => GreaterEqual,
                 '>' => Greater,
@@ -76,34 +94,114 @@ impl<'src> TokenStream<'src> {
                 '<' => Less,
                 '&' if self.consume('&') => And,
                 '|' if self.consume('|') => Or,
+                '|' => Pipe,
                 '"' => self.consume_string(),
+                c if c.is_ascii_digit() => self.consume_int(),
                 c if can_start_identifier(c) => self.consume_keyword_or_identifier(),
                 _ => Unknown,
             }
         })
     }
 
+    // Moves the start of the current token past everything consumed so far,
+    // i.e. the previous token & any whitespace following it.
     fn prepare_for_next_token(&mut self) {
-        self.current_token_start = self.current_token_start + self.current_token_size;
+        self.current_token_start += self.current_token_size;
         self.current_token_size = 0;
+        self.current_token_line = self.line;
+        self.current_token_column = self.column;
     }
 
     // Assumes that we've read a complete token & wish to skip
-    // all whitespace until the next token. As such it bumps
-    // current_token_start on each skipped char.
+    // all whitespace until the next token.
     fn skip_whitespace(&mut self) {
-        while self.src.peek().map_or(false, |c| c.is_whitespace()) {
-            self.src.next();
-            self.current_token_start += 1;
+        while self.src.peek().is_some_and(|c| c.is_whitespace()) {
+            self.advance();
         }
     }
 
-    // Assumes we have already read a '"' and then
-    // keeps reading until it finds another '"'.
+    // Assumes we have already read a '"' and then keeps reading until
+    // it finds another (unescaped) '"', decoding escapes along the way.
     fn consume_string(&mut self) -> TokenKind {
-        self.consume_until_match('"');
-        self.consume('"');
-        TokenKind::String(self.lexeme().trim_matches('"').to_string())
+        let mut value = String::new();
+        let mut error = None;
+
+        loop {
+            match self.advance() {
+                None => return TokenKind::Error(LexError::UnterminatedString),
+                Some('"') => break,
+                Some('\\') => match self.consume_escape() {
+                    Ok(c) => value.push(c),
+                    // Keep going so that the whole literal ends up in the token.
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                },
+                Some(c) => value.push(c),
+            }
+        }
+
+        match error {
+            Some(e) => TokenKind::Error(e),
+            None => TokenKind::String(value),
+        }
+    }
+
+    // Assumes we have already read a '\\'.
+    fn consume_escape(&mut self) -> Result<char, LexError> {
+        match self.advance() {
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('0') => Ok('\0'),
+            Some('u') => self.consume_unicode_escape(),
+            Some(c) => Err(LexError::InvalidEscape(format!("\\{}", c))),
+            None => Err(LexError::UnterminatedString),
+        }
+    }
+
+    // Assumes we have already read `\u`, and expects 1-6 hex digits in braces.
+    fn consume_unicode_escape(&mut self) -> Result<char, LexError> {
+        let mut sequence = String::from("\\u");
+        if !self.consume('{') {
+            return Err(LexError::InvalidEscape(sequence));
+        }
+        sequence.push('{');
+
+        let mut digits = String::new();
+        while let Some(c) = self.src.peek().copied().filter(char::is_ascii_hexdigit) {
+            self.advance();
+            digits.push(c);
+        }
+        sequence.push_str(&digits);
+
+        if !self.consume('}') {
+            return Err(LexError::InvalidEscape(sequence));
+        }
+        sequence.push('}');
+
+        Some(digits)
+            .filter(|d| !d.is_empty() && d.len() <= 6)
+            .and_then(|d| u32::from_str_radix(&d, 16).ok())
+            .and_then(char::from_u32)
+            .ok_or(LexError::InvalidEscape(sequence))
+    }
+
+    // Assumes we have already read a digit. Letters directly after the
+    // digits are part of the same (invalid) literal, rather than the start
+    // of an identifier.
+    fn consume_int(&mut self) -> TokenKind {
+        self.consume_until(cannot_be_used_in_identifier);
+        let lexeme = self.lexeme();
+        if !lexeme.chars().all(|c| c.is_ascii_digit()) {
+            return TokenKind::Error(LexError::InvalidInteger(lexeme.to_string()));
+        }
+        lexeme
+            .parse()
+            .map(TokenKind::Int)
+            .unwrap_or(TokenKind::Error(LexError::IntegerOutOfRange))
     }
 
     fn consume_keyword_or_identifier(&mut self) -> TokenKind {
@@ -114,10 +212,21 @@ impl<'src> TokenStream<'src> {
             .unwrap_or(TokenKind::Identifier)
     }
 
+    fn advance(&mut self) -> Option<char> {
+        let next = self.src.next()?;
+        self.current_token_size += next.len_utf8();
+        if next == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(next)
+    }
+
     fn consume(&mut self, c: char) -> bool {
         if self.src.peek() == Some(&c) {
-            self.src.next();
-            self.current_token_size += 1;
+            self.advance();
             true
         } else {
             false
@@ -129,21 +238,25 @@ impl<'src> TokenStream<'src> {
         &self.raw[self.current_token_start..token_end]
     }
 
+    fn span(&self) -> Span {
+        Span::new(
+            self.current_token_start,
+            self.current_token_start + self.current_token_size,
+            self.current_token_line,
+            self.current_token_column,
+        )
+    }
+
     fn consume_until(&mut self, should_stop: impl Fn(char) -> bool) {
         let is_done = |nxt: Option<&char>| nxt.is_none() || should_stop(*nxt.unwrap());
         while !is_done(self.src.peek()) {
-            self.src.next();
-            self.current_token_size += 1;
+            self.advance();
         }
     }
-
-    fn consume_until_match(&mut self, sought: char) {
-        self.consume_until(|c| c == sought)
-    }
 }
 
 fn cannot_be_used_in_identifier(c: char) -> bool {
-    !(can_start_identifier(c) || c.is_digit(10))
+    !(can_start_identifier(c) || c.is_ascii_digit())
 }
 
 fn can_start_identifier(c: char) -> bool {
@@ -185,6 +298,7 @@ mod tests {
         [ "-"        , TokenKind::Minus        ]
         [ "*"        , TokenKind::Star         ]
         [ "/"        , TokenKind::Slash        ]
+        [ "%"        , TokenKind::Percent      ]
         [ "="        , TokenKind::Equal        ]
         [ ">"        , TokenKind::Greater      ]
         [ "<"        , TokenKind::Less         ]
@@ -195,6 +309,8 @@ mod tests {
         [ "!="       , TokenKind::BangEqual    ]
         [ "&&"       , TokenKind::And          ]
         [ "||"       , TokenKind::Or           ]
+        [ "=>"       , TokenKind::FatArrow     ]
+        [ "|"        , TokenKind::Pipe         ]
         [ "let"      , TokenKind::Let          ]
         [ "mut"      , TokenKind::Mut          ]
         [ "fn"       , TokenKind::Fn           ]
@@ -203,7 +319,13 @@ mod tests {
         [ "interface", TokenKind::Interface    ]
         [ "impl"     , TokenKind::Impl         ]
         [ "check"    , TokenKind::Check        ]
+        [ "checking" , TokenKind::Checking     ]
         [ "match"    , TokenKind::Match        ]
+        [ "true"     , TokenKind::True         ]
+        [ "false"    , TokenKind::False        ]
+        [ "if"       , TokenKind::If           ]
+        [ "else"     , TokenKind::Else         ]
+        [ "while"    , TokenKind::While        ]
     )]
     fn can_lex_static_tokens(input: &str, expected: TokenKind) {
         let maybe_token = token_stream(input).next();
@@ -212,6 +334,7 @@ mod tests {
                 &Token {
                     kind: expected,
                     lexeme: input.to_string(),
+                    span: util::span_of(input),
                 },
                 &token,
                 input,
@@ -252,6 +375,90 @@ mod tests {
         }
     }
 
+    #[test_with_parameters(
+        [ input            , line, column ]
+        [ "\""            , 1   , 1      ]
+        [ "\"abc"         , 1   , 1      ]
+        [ "a\n  \"b\nc"   , 2   , 3      ]
+    )]
+    fn unterminated_string_is_an_error_at_opening_quote(input: &str, line: usize, column: usize) {
+        let last = token_stream(input).last().expect("No tokens returned");
+        assert_eq!(TokenKind::Error(LexError::UnterminatedString), last.kind);
+        assert_eq!((line, column), (last.span.line, last.span.column));
+        assert_eq!(input.len(), last.span.end);
+    }
+
+    #[test_with_parameters(
+        [ input                  , expected_literal      ]
+        [ r#""\"""#              , "\""                  ]
+        [ r#""a\\b""#            , "a\\b"                ]
+        [ r#""1\n2\t3\r4\0""#    , "1\n2\t3\r4\0"        ]
+        [ r#""\u{1F95A} \u{41}""# , "\u{1F95A} A"         ]
+    )]
+    fn decodes_escape_sequences(input: &str, expected: &str) {
+        let tokens: Vec<Token> = token_stream(input).collect();
+        assert_eq!(vec![util::string_token(expected, input)], tokens);
+    }
+
+    #[test_with_parameters(
+        [ input                 , sequence      ]
+        [ r#""\q""#             , "\\q"        ]
+        [ r#""a\ b""#           , "\\ "        ]
+        [ r#""\u41""#           , "\\u"        ]
+        [ r#""\u{41""#          , "\\u{41"     ]
+        [ r#""\u{}""#           , "\\u{}"      ]
+        [ r#""\u{1234567}""#    , "\\u{1234567}" ]
+        [ r#""\u{D800}""#       , "\\u{D800}"  ]
+        [ r#""\x" "\y""#        , "\\x"        ]
+    )]
+    fn invalid_escape_sequences_are_errors(input: &str, sequence: &str) {
+        let token = token_stream(input).next().expect("No token returned");
+        assert_eq!(
+            TokenKind::Error(LexError::InvalidEscape(sequence.to_string())),
+            token.kind
+        );
+        assert_eq!(0, token.span.start);
+    }
+
+    #[test_with_parameters(
+        [ input                  , expected            ]
+        [ "0"                    , 0                   ]
+        [ "7"                    , 7                   ]
+        [ "500"                  , 500                 ]
+        [ "007"                  , 7                   ]
+        [ "9223372036854775807"  , i64::MAX            ]
+    )]
+    fn can_lex_integer_literals(input: &str, expected: i64) {
+        let tokens: Vec<Token> = token_stream(input).collect();
+        let expected = vec![Token {
+            kind: TokenKind::Int(expected),
+            lexeme: input.to_string(),
+            span: util::span_of(input),
+        }];
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn integer_literals_followed_by_letters_are_errors() {
+        use TokenKind::*;
+        let tokens: Vec<_> = token_stream("12ab 3_ 4;").map(|t| t.kind).collect();
+        assert_eq!(
+            vec![
+                Error(LexError::InvalidInteger("12ab".to_string())),
+                Error(LexError::InvalidInteger("3_".to_string())),
+                Int(4),
+                SemiColon
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn overlarge_integer_literal_is_an_error() {
+        let token = token_stream("9223372036854775808").next().unwrap();
+        assert_eq!(TokenKind::Error(LexError::IntegerOutOfRange), token.kind);
+    }
+
     #[test]
     fn correctly_skips_whitespace() {
         let input = "\"b\", \"c\"";
@@ -260,19 +467,41 @@ mod tests {
             Token {
                 kind: TokenKind::String("b".into()),
                 lexeme: "\"b\"".into(),
+                span: Span::new(0, 3, 1, 1),
             },
             Token {
                 kind: TokenKind::Comma,
                 lexeme: ",".into(),
+                span: Span::new(3, 4, 1, 4),
             },
             Token {
                 kind: TokenKind::String("c".into()),
                 lexeme: "\"c\"".into(),
+                span: Span::new(5, 8, 1, 6),
             },
         ];
         assert_eq!(expected, tokens);
     }
 
+    #[test_with_parameters(
+        [ input             , start, end, line, column ]
+        [ "abc"             , 0    , 3  , 1   , 1      ]
+        [ "   abc"          , 3    , 6  , 1   , 4      ]
+        [ "\n\n  abc"       , 4    , 7  , 3   , 3      ]
+        [ "\"ü\" abc"       , 5    , 8  , 1   , 5      ]
+        [ "\"a\nb\"\n abc" , 7    , 10 , 3   , 2      ]
+    )]
+    fn tracks_token_spans(input: &str, start: usize, end: usize, line: usize, column: usize) {
+        let last = token_stream(input).last().expect("No tokens returned");
+        let span = last.span;
+        assert_eq!(
+            (start, end, line, column),
+            (span.start, span.end, span.line, span.column),
+            "Incorrect span for last token of input {:?}",
+            input
+        );
+    }
+
     mod util {
         use super::super::*;
 
@@ -284,10 +513,17 @@ mod tests {
             )
         }
 
+        /// The span of a token which is the whole of a single line of
+        /// input.
+        pub(crate) fn span_of(lexeme: &str) -> Span {
+            Span::new(0, lexeme.len(), 1, 1)
+        }
+
         pub(crate) fn ident_token(lexeme: &str) -> Token {
             Token {
                 kind: TokenKind::Identifier,
                 lexeme: lexeme.to_string(),
+                span: span_of(lexeme),
             }
         }
 
@@ -295,6 +531,7 @@ mod tests {
             Token {
                 kind: TokenKind::String(value.into()),
                 lexeme: lexeme.to_string(),
+                span: span_of(lexeme),
             }
         }
     }
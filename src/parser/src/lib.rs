@@ -1,92 +1,947 @@
-use ast::{Expr, Literal, Stmt, UntypedProgram};
+use ast::{
+    Block, CheckDecl, EnumDecl, Expr, Field, FieldInit, FnDecl, FnSignature, ImplDecl,
+    InterfaceDecl, Literal, MatchArm, Param, Pattern, RecordDecl, Stmt, TypeExpr, UntypedProgram,
+    Variant,
+};
 use lexer::TokenStream;
-use std::iter::Peekable;
+use std::{fmt, iter::Peekable};
 use token::*;
 
-pub fn parse<'src>(stream: TokenStream<'src>) -> UntypedProgram {
+pub fn parse(stream: TokenStream<'_>) -> Result<UntypedProgram, Vec<ParseError>> {
     let mut parser = Parser::new(stream);
     parser.parse_program()
 }
 
+/// Describes a point at which the token stream didn't match the grammar.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    /// The kinds of token which would have been accepted at this point.
+    pub expected: Vec<TokenKind>,
+    /// The token which was found instead, or `None` if the input ended.
+    pub found: Option<Token>,
+    pub span: Span,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Whatever we were expecting, an invalid token is the real problem.
+        if let Some(Token {
+            kind: TokenKind::Error(e),
+            ..
+        }) = &self.found
+        {
+            return write!(f, "{}: {}", self.span, e);
+        }
+
+        write!(f, "{}: expected ", self.span)?;
+        if self.expected == expression_start_kinds() {
+            write!(f, "expression")?;
+        } else if self.expected == pattern_start_kinds() {
+            write!(f, "pattern")?;
+        } else {
+            for (i, kind) in self.expected.iter().enumerate() {
+                match i {
+                    0 => {}
+                    _ if i == self.expected.len() - 1 => write!(f, " or ")?,
+                    _ => write!(f, ", ")?,
+                }
+                write!(f, "{}", kind)?;
+            }
+        }
+        match &self.found {
+            Some(t) => write!(f, ", found `{}`", t.lexeme),
+            None => write!(f, ", found end of input"),
+        }
+    }
+}
+
+/// The kinds of token which may begin an expression.
+fn expression_start_kinds() -> Vec<TokenKind> {
+    vec![
+        TokenKind::Identifier,
+        TokenKind::String(String::new()),
+        TokenKind::Int(0),
+        TokenKind::True,
+        TokenKind::False,
+        TokenKind::LeftParen,
+        TokenKind::LeftBrace,
+        TokenKind::If,
+        TokenKind::While,
+        TokenKind::Match,
+        TokenKind::Bang,
+        TokenKind::Minus,
+    ]
+}
+
+/// The kinds of token which may begin a pattern.
+fn pattern_start_kinds() -> Vec<TokenKind> {
+    vec![
+        TokenKind::Identifier,
+        TokenKind::String(String::new()),
+        TokenKind::Int(0),
+        TokenKind::True,
+        TokenKind::False,
+        TokenKind::LeftParen,
+        TokenKind::Minus,
+    ]
+}
+
+/// How tightly binary operators bind, from loosest to tightest.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Precedence {
+    Lowest,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+}
+
+impl Precedence {
+    fn of_binary(kind: &TokenKind) -> Option<Precedence> {
+        use TokenKind::*;
+
+        match kind {
+            Or => Some(Precedence::Or),
+            And => Some(Precedence::And),
+            EqualEqual | BangEqual => Some(Precedence::Equality),
+            Less | LessEqual | Greater | GreaterEqual => Some(Precedence::Comparison),
+            Plus | Minus => Some(Precedence::Term),
+            Star | Slash | Percent => Some(Precedence::Factor),
+            _ => None,
+        }
+    }
+}
+
+// Errors are boxed while they're propagated, as they're much larger than
+// most of the values we parse.
+type ParseResult<T> = Result<T, Box<ParseError>>;
+
 struct Parser<T> {
     tokens: T,
+    // The span of the most recently consumed token, and the empty span just
+    // after it. The latter locates errors which occur at the end of input.
+    previous_span: Span,
+    previous_end: Span,
+    // In positions like `if x {`, the `{` begins a block rather than a
+    // record literal, so record literals are only parsed when this is set.
+    record_literals_allowed: bool,
 }
 
 impl<T: Iterator<Item = Token>> Parser<Peekable<T>> {
     fn new(tokens: T) -> Parser<Peekable<T>> {
         let tokens = tokens.peekable();
-        Parser { tokens }
+        Parser {
+            tokens,
+            previous_span: Span::default(),
+            previous_end: Span::default(),
+            record_literals_allowed: true,
+        }
     }
 
-    fn parse_program(&mut self) -> UntypedProgram {
+    fn parse_program(&mut self) -> Result<UntypedProgram, Vec<ParseError>> {
         let mut stmts = Vec::new();
+        let mut errors = Vec::new();
+
+        while self.tokens.peek().is_some() {
+            match self.parse_stmt() {
+                Ok(s) => stmts.push(s),
+                Err(e) => {
+                    errors.push(*e);
+                    self.synchronize();
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(UntypedProgram { stmts })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Discards tokens until we reach what is probably the start of the next
+    /// statement, so that we can carry on parsing after an error. A token
+    /// which begins a declaration is left for the next statement to consume.
+    fn synchronize(&mut self) {
+        while let Some(t) = self.tokens.peek() {
+            let starts_declaration = matches!(
+                t.kind,
+                TokenKind::Fn
+                    | TokenKind::Let
+                    | TokenKind::Mut
+                    | TokenKind::Record
+                    | TokenKind::Enum
+                    | TokenKind::Interface
+                    | TokenKind::Impl
+                    | TokenKind::Check
+            );
+            if starts_declaration {
+                return;
+            }
+
+            let t = self.advance().unwrap();
+            if matches!(t.kind, TokenKind::SemiColon | TokenKind::RightBrace) {
+                return;
+            }
+        }
+    }
+
+    fn parse_stmt(&mut self) -> ParseResult<Stmt> {
+        if self.match_single(TokenKind::Let).is_some() {
+            let (name, ty, initializer) = self.binding()?;
+            Ok(Stmt::Let {
+                name,
+                ty,
+                initializer,
+            })
+        } else if self.match_single(TokenKind::Mut).is_some() {
+            let (name, ty, initializer) = self.binding()?;
+            Ok(Stmt::Mut {
+                name,
+                ty,
+                initializer,
+            })
+        } else if self.match_single(TokenKind::Fn).is_some() {
+            Ok(Stmt::Fn {
+                decl: self.fn_declaration()?,
+            })
+        } else if self.match_single(TokenKind::Record).is_some() {
+            Ok(Stmt::Record {
+                decl: self.record_declaration()?,
+            })
+        } else if self.match_single(TokenKind::Enum).is_some() {
+            Ok(Stmt::Enum {
+                decl: self.enum_declaration()?,
+            })
+        } else if self.match_single(TokenKind::Interface).is_some() {
+            Ok(Stmt::Interface {
+                decl: self.interface_declaration()?,
+            })
+        } else if self.match_single(TokenKind::Impl).is_some() {
+            Ok(Stmt::Impl {
+                decl: self.impl_declaration()?,
+            })
+        } else if self.match_single(TokenKind::Check).is_some() {
+            Ok(Stmt::Check {
+                decl: self.check_declaration()?,
+            })
+        } else {
+            self.expression_statement()
+        }
+    }
+
+    /// Assumes that we have already parsed `fn`.
+    fn fn_declaration(&mut self) -> ParseResult<FnDecl> {
+        let FnSignature {
+            name,
+            params,
+            return_type,
+        } = self.fn_signature()?;
+
+        if !self.check_next(TokenKind::LeftBrace) {
+            return Err(self.error(match return_type {
+                Some(_) => vec![TokenKind::LeftBrace],
+                None => vec![TokenKind::RightArrow, TokenKind::LeftBrace],
+            }));
+        }
+        let body = self.block()?;
+
+        Ok(FnDecl {
+            name,
+            params,
+            return_type,
+            body,
+        })
+    }
+
+    /// Assumes that we have already parsed `record`.
+    fn record_declaration(&mut self) -> ParseResult<RecordDecl> {
+        let name = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::LeftBrace)?;
+        let fields = self.comma_separated(TokenKind::RightBrace, Self::field)?;
+        Ok(RecordDecl { name, fields })
+    }
+
+    /// Assumes that we have already parsed `enum`.
+    fn enum_declaration(&mut self) -> ParseResult<EnumDecl> {
+        let name = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::LeftBrace)?;
+        let variants = self.comma_separated(TokenKind::RightBrace, |p| {
+            let name = p.expect(TokenKind::Identifier)?;
+            let fields = match p.match_single(TokenKind::LeftParen) {
+                Some(_) => p.comma_separated(TokenKind::RightParen, Self::field)?,
+                None => Vec::new(),
+            };
+            Ok(Variant { name, fields })
+        })?;
+        Ok(EnumDecl { name, variants })
+    }
+
+    /// Assumes that we have already parsed `fn`.
+    fn fn_signature(&mut self) -> ParseResult<FnSignature> {
+        let name = self.expect(TokenKind::Identifier)?;
+
+        self.expect(TokenKind::LeftParen)?;
+        let params = self.comma_separated(TokenKind::RightParen, Self::parameter)?;
 
-        let ps = self.parse_stmt();
-        if let Some(s) = ps {
-            stmts.push(s);
+        let return_type = match self.match_single(TokenKind::RightArrow) {
+            Some(_) => Some(self.type_expr()?),
+            None => None,
+        };
+
+        Ok(FnSignature {
+            name,
+            params,
+            return_type,
+        })
+    }
+
+    /// Assumes that we have already parsed `interface`.
+    fn interface_declaration(&mut self) -> ParseResult<InterfaceDecl> {
+        let name = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::LeftBrace)?;
+        let mut methods = Vec::new();
+        while self.match_single(TokenKind::RightBrace).is_none() {
+            if self.match_single(TokenKind::Fn).is_none() {
+                return Err(self.error(vec![TokenKind::Fn, TokenKind::RightBrace]));
+            }
+            methods.push(self.fn_signature()?);
         }
+        Ok(InterfaceDecl { name, methods })
+    }
 
-        UntypedProgram { stmts }
+    /// Assumes that we have already parsed `impl`.
+    fn impl_declaration(&mut self) -> ParseResult<ImplDecl> {
+        let type_name = self.expect(TokenKind::Identifier)?;
+        let interface = match self.match_single(TokenKind::Colon) {
+            Some(_) => Some(self.expect(TokenKind::Identifier)?),
+            None => None,
+        };
+        if !self.check_next(TokenKind::LeftBrace) {
+            return Err(self.error(match interface {
+                Some(_) => vec![TokenKind::LeftBrace],
+                None => vec![TokenKind::Colon, TokenKind::LeftBrace],
+            }));
+        }
+        self.expect(TokenKind::LeftBrace)?;
+        let mut methods = Vec::new();
+        while self.match_single(TokenKind::RightBrace).is_none() {
+            if self.match_single(TokenKind::Fn).is_none() {
+                return Err(self.error(vec![TokenKind::Fn, TokenKind::RightBrace]));
+            }
+            methods.push(self.fn_declaration()?);
+        }
+        Ok(ImplDecl {
+            type_name,
+            interface,
+            methods,
+        })
     }
 
-    fn parse_stmt(&mut self) -> Option<Stmt> {
-        if self.tokens.peek().is_none() {
-            return None;
+    /// Assumes that we have already parsed `check`.
+    fn check_declaration(&mut self) -> ParseResult<CheckDecl> {
+        let name = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::Less)?;
+        let subject = self.type_expr()?;
+        self.expect(TokenKind::Greater)?;
+        let params = match self.match_single(TokenKind::LeftParen) {
+            Some(_) => Some(self.comma_separated(TokenKind::RightParen, Self::parameter)?),
+            None => None,
+        };
+        if !self.check_next(TokenKind::LeftBrace) {
+            return Err(self.error(match params {
+                Some(_) => vec![TokenKind::LeftBrace],
+                None => vec![TokenKind::LeftParen, TokenKind::LeftBrace],
+            }));
         }
+        let body = self.block()?;
+        Ok(CheckDecl {
+            name,
+            subject,
+            params: params.unwrap_or_default(),
+            body,
+        })
+    }
+
+    fn parameter(&mut self) -> ParseResult<Param> {
+        let name = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::Colon)?;
+        let ty = self.type_expr()?;
+        Ok(Param { name, ty })
+    }
+
+    fn field(&mut self) -> ParseResult<Field> {
+        let name = self.expect(TokenKind::Identifier)?;
+        self.expect(TokenKind::Colon)?;
+        let ty = self.type_expr()?;
+        Ok(Field { name, ty })
+    }
+
+    /// Parses `item`s separated by commas, with an optional trailing comma,
+    /// up to & including the `close` token.
+    fn comma_separated<I>(
+        &mut self,
+        close: TokenKind,
+        mut item: impl FnMut(&mut Self) -> ParseResult<I>,
+    ) -> ParseResult<Vec<I>> {
+        let mut items = Vec::new();
+        while !self.check_next(close.clone()) {
+            items.push(item(self)?);
+            if self.match_single(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+        if self.match_single(close.clone()).is_none() {
+            return Err(self.error(vec![TokenKind::Comma, close]));
+        }
+        Ok(items)
+    }
 
-        self.expression_statement()
+    /// Parses `f` with record literals allowed (or not), restoring the
+    /// previous setting afterwards.
+    fn with_record_literals<R>(
+        &mut self,
+        allowed: bool,
+        f: impl FnOnce(&mut Self) -> ParseResult<R>,
+    ) -> ParseResult<R> {
+        let previous = std::mem::replace(&mut self.record_literals_allowed, allowed);
+        let result = f(self);
+        self.record_literals_allowed = previous;
+        result
     }
 
-    fn expression_statement(&mut self) -> Option<Stmt> {
-        let expression = self.expression();
+    fn block(&mut self) -> ParseResult<Block> {
+        self.with_record_literals(true, |p| p.block_contents())
+    }
+
+    fn block_contents(&mut self) -> ParseResult<Block> {
+        let open = self.expect(TokenKind::LeftBrace)?;
+        let mut stmts = Vec::new();
+
+        let tail = loop {
+            if let Some(close) = self.match_single(TokenKind::RightBrace) {
+                return Ok(Block {
+                    stmts,
+                    tail: None,
+                    span: open.span.to(close.span),
+                });
+            }
+
+            if !self.starts_expression_statement() {
+                stmts.push(self.parse_stmt()?);
+                continue;
+            }
+
+            let block_like = self.starts_block_like();
+            let e = if block_like {
+                self.block_like()?
+            } else {
+                self.expression()?
+            };
+
+            if self.match_single(TokenKind::SemiColon).is_some() {
+                stmts.push(Stmt::Expr { e });
+            } else if self.check_next(TokenKind::RightBrace) {
+                break e;
+            } else if block_like {
+                stmts.push(Stmt::Expr { e });
+            } else {
+                return Err(self.error(vec![TokenKind::SemiColon, TokenKind::RightBrace]));
+            }
+        };
+
+        let close = self.expect(TokenKind::RightBrace)?;
+        Ok(Block {
+            stmts,
+            tail: Some(Box::new(tail)),
+            span: open.span.to(close.span),
+        })
+    }
 
-        self.match_single(TokenKind::SemiColon)
-            .and_then(|_t| expression.map(|e| Stmt::Expr { e }))
+    /// Whether the next token begins an expression which ends in a block.
+    /// These may be used as statements without a trailing `;`, and so end
+    /// the statement as soon as their block does.
+    fn starts_block_like(&mut self) -> bool {
+        matches!(
+            self.tokens.peek().map(|t| &t.kind),
+            Some(TokenKind::LeftBrace | TokenKind::If | TokenKind::While | TokenKind::Match)
+        )
     }
 
-    fn expression(&mut self) -> Option<Expr> {
-        match self.primary() {
-            Some(e) if self.match_single(TokenKind::LeftParen).is_some() => {
-                self.function_application_args(e)
+    fn block_like(&mut self) -> ParseResult<Expr> {
+        if let Some(keyword) = self.match_single(TokenKind::If) {
+            self.if_expression(keyword)
+        } else if let Some(keyword) = self.match_single(TokenKind::While) {
+            Ok(Expr::While {
+                keyword,
+                condition: Box::new(self.condition()?),
+                body: self.block()?,
+            })
+        } else if let Some(keyword) = self.match_single(TokenKind::Match) {
+            self.match_expression(keyword)
+        } else {
+            Ok(Expr::Block {
+                block: self.block()?,
+            })
+        }
+    }
+
+    /// Assumes that we have already parsed `match`.
+    fn match_expression(&mut self, keyword: Token) -> ParseResult<Expr> {
+        let scrutinee = Box::new(self.condition()?);
+        self.expect(TokenKind::LeftBrace)?;
+        let arms = self.with_record_literals(true, |p| p.match_arms())?;
+        Ok(Expr::Match {
+            scrutinee,
+            arms,
+            span: keyword.span.to(self.previous_span),
+        })
+    }
+
+    /// Parses arms up to & including the closing brace. As with statements,
+    /// an arm whose body is block-like doesn't need a trailing comma.
+    fn match_arms(&mut self) -> ParseResult<Vec<MatchArm>> {
+        let mut arms = Vec::new();
+        while !self.check_next(TokenKind::RightBrace) {
+            let pattern = self.pattern()?;
+            self.expect(TokenKind::FatArrow)?;
+            let block_like = self.starts_block_like();
+            let body = self.expression()?;
+            arms.push(MatchArm { pattern, body });
+
+            if self.match_single(TokenKind::Comma).is_none() && !block_like {
+                break;
             }
-            Some(e) => Some(e),
+        }
+        if self.match_single(TokenKind::RightBrace).is_none() {
+            return Err(self.error(vec![TokenKind::Comma, TokenKind::RightBrace]));
+        }
+        Ok(arms)
+    }
+
+    /// A top-level pattern, where a comma separated sequence of patterns is
+    /// a tuple pattern even without surrounding parentheses.
+    fn pattern(&mut self) -> ParseResult<Pattern> {
+        let first = self.or_pattern()?;
+        if !self.check_next(TokenKind::Comma) {
+            return Ok(first);
+        }
+        let mut elements = vec![first];
+        while self.match_single(TokenKind::Comma).is_some() {
+            elements.push(self.or_pattern()?);
+        }
+        Ok(Pattern::Tuple {
+            span: elements[0].span().to(self.previous_span),
+            elements,
+        })
+    }
+
+    fn or_pattern(&mut self) -> ParseResult<Pattern> {
+        let mut alternatives = vec![self.single_pattern()?];
+        while self.match_single(TokenKind::Pipe).is_some() {
+            alternatives.push(self.single_pattern()?);
+        }
+        if alternatives.len() == 1 {
+            Ok(alternatives.remove(0))
+        } else {
+            Ok(Pattern::Or { alternatives })
+        }
+    }
+
+    fn single_pattern(&mut self) -> ParseResult<Pattern> {
+        if let Some((l, span)) = self.literal() {
+            return Ok(Pattern::Literal { l, span });
+        }
+        if let Some(minus) = self.match_single(TokenKind::Minus) {
+            return match self.literal() {
+                Some((Literal::Int(i), span)) => Ok(Pattern::Literal {
+                    l: Literal::Int(-i),
+                    span: minus.span.to(span),
+                }),
+                _ => Err(self.error(vec![TokenKind::Int(0)])),
+            };
+        }
+        if let Some(open) = self.match_single(TokenKind::LeftParen) {
+            let mut elements = self.comma_separated(TokenKind::RightParen, Self::or_pattern)?;
+            return match elements.len() {
+                1 => Ok(elements.remove(0)),
+                _ => Ok(Pattern::Tuple {
+                    elements,
+                    span: open.span.to(self.previous_span),
+                }),
+            };
+        }
+
+        let Some(first) = self.match_single(TokenKind::Identifier) else {
+            return Err(self.error(pattern_start_kinds()));
+        };
+        if first.lexeme == "_" {
+            return Ok(Pattern::Wildcard { span: first.span });
+        }
+        let (enum_name, name) = match self.match_single(TokenKind::Dot) {
+            Some(_) => (Some(first), self.expect(TokenKind::Identifier)?),
+            None if self.check_next(TokenKind::LeftParen) => (None, first),
+            None => return Ok(Pattern::Identifier { name: first }),
+        };
+        let fields = match self.match_single(TokenKind::LeftParen) {
+            Some(_) => Some(self.comma_separated(TokenKind::RightParen, Self::or_pattern)?),
+            None => None,
+        };
+        let start = enum_name.as_ref().unwrap_or(&name).span;
+        Ok(Pattern::Variant {
+            span: start.to(self.previous_span),
+            enum_name,
+            name,
+            fields,
+        })
+    }
+
+    /// An expression which is immediately followed by a block.
+    fn condition(&mut self) -> ParseResult<Expr> {
+        self.with_record_literals(false, |p| p.expression())
+    }
+
+    /// Assumes that we have already parsed `if`.
+    fn if_expression(&mut self, keyword: Token) -> ParseResult<Expr> {
+        let condition = Box::new(self.condition()?);
+        let then_branch = self.block()?;
+
+        let else_branch = match self.match_single(TokenKind::Else) {
+            None => None,
+            Some(_) => match self.match_single(TokenKind::If) {
+                Some(keyword) => Some(self.if_expression(keyword)?),
+                None if self.check_next(TokenKind::LeftBrace) => Some(Expr::Block {
+                    block: self.block()?,
+                }),
+                None => return Err(self.error(vec![TokenKind::If, TokenKind::LeftBrace])),
+            },
+        };
+
+        Ok(Expr::If {
+            keyword,
+            condition,
+            then_branch,
+            else_branch: else_branch.map(Box::new),
+        })
+    }
+
+    fn starts_expression_statement(&mut self) -> bool {
+        !matches!(
+            self.tokens.peek().map(|t| &t.kind),
+            Some(
+                TokenKind::Let
+                    | TokenKind::Mut
+                    | TokenKind::Fn
+                    | TokenKind::Record
+                    | TokenKind::Enum
+                    | TokenKind::Interface
+                    | TokenKind::Impl
+                    | TokenKind::Check
+            )
+        )
+    }
+
+    /// Assumes that we have already parsed `let` or `mut`, and parses the
+    /// rest of the binding.
+    fn binding(&mut self) -> ParseResult<(Token, Option<TypeExpr>, Expr)> {
+        let name = self.expect(TokenKind::Identifier)?;
+
+        let ty = match self.match_single(TokenKind::Colon) {
+            Some(_) => Some(self.type_expr()?),
             None => None,
+        };
+
+        if self.match_single(TokenKind::Equal).is_none() {
+            return Err(self.error(match ty {
+                Some(_) => vec![TokenKind::Equal],
+                None => vec![TokenKind::Colon, TokenKind::Equal],
+            }));
+        }
+        let initializer = self.expression()?;
+        self.expect(TokenKind::SemiColon)?;
+
+        Ok((name, ty, initializer))
+    }
+
+    fn type_expr(&mut self) -> ParseResult<TypeExpr> {
+        let mut ty = self.prefixed_type()?;
+
+        while self.match_single(TokenKind::Checking).is_some() {
+            let check = self.expect(TokenKind::Identifier)?;
+            let (args, end) = match self.match_single(TokenKind::LeftParen) {
+                Some(_) => {
+                    let (args, paren) = self.with_record_literals(true, |p| p.arguments())?;
+                    (args, paren.span)
+                }
+                None => (Vec::new(), check.span),
+            };
+            ty = TypeExpr::Checking {
+                span: ty.span().to(end),
+                base: Box::new(ty),
+                check,
+                args,
+            };
+        }
+
+        Ok(ty)
+    }
+
+    /// A type's name, optionally preceded by checks which refine it, e.g.
+    /// `non_zero Int` as a shorthand for `Int checking non_zero`. Checks are
+    /// named in snake_case & types in UpperCamelCase, so only a lowercase
+    /// name can be a check.
+    fn prefixed_type(&mut self) -> ParseResult<TypeExpr> {
+        let name = self.expect(TokenKind::Identifier)?;
+        if !name.lexeme.starts_with(char::is_lowercase) {
+            return Ok(TypeExpr::Named { name });
+        }
+        let args = match self.match_single(TokenKind::LeftParen) {
+            Some(_) => self.with_record_literals(true, |p| p.arguments())?.0,
+            None if self.check_next(TokenKind::Identifier) => Vec::new(),
+            None => return Ok(TypeExpr::Named { name }),
+        };
+        let base = self.prefixed_type()?;
+        Ok(TypeExpr::Checking {
+            span: name.span.to(base.span()),
+            base: Box::new(base),
+            check: name,
+            args,
+        })
+    }
+
+    fn expression_statement(&mut self) -> ParseResult<Stmt> {
+        if self.starts_block_like() {
+            let e = self.block_like()?;
+            self.match_single(TokenKind::SemiColon);
+            return Ok(Stmt::Expr { e });
+        }
+
+        let e = self.expression()?;
+        self.expect(TokenKind::SemiColon)?;
+        Ok(Stmt::Expr { e })
+    }
+
+    fn expression(&mut self) -> ParseResult<Expr> {
+        let e = self.binary(Precedence::Lowest)?;
+
+        // Only bindings can be assigned to, so anything else is left for
+        // the caller to reject the `=`.
+        if let Expr::Identifier { name } = e {
+            return match self.match_single(TokenKind::Equal) {
+                Some(_) => Ok(Expr::Assign {
+                    name,
+                    value: Box::new(self.expression()?),
+                }),
+                None => Ok(Expr::Identifier { name }),
+            };
+        }
+
+        Ok(e)
+    }
+
+    /// Parses a chain of binary operators which bind more tightly than
+    /// `min`. All binary operators are left-associative.
+    fn binary(&mut self, min: Precedence) -> ParseResult<Expr> {
+        let mut left = self.unary()?;
+
+        loop {
+            let precedence = self
+                .tokens
+                .peek()
+                .and_then(|t| Precedence::of_binary(&t.kind));
+            match precedence {
+                Some(p) if p > min => {
+                    let op = self.advance().expect("Operator was peeked");
+                    let right = self.binary(p)?;
+                    left = Expr::Binary {
+                        left: Box::new(left),
+                        op,
+                        right: Box::new(right),
+                    };
+                }
+                _ => return Ok(left),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> ParseResult<Expr> {
+        let op = self
+            .match_single(TokenKind::Bang)
+            .or_else(|| self.match_single(TokenKind::Minus));
+        match op {
+            Some(op) => Ok(Expr::Unary {
+                op,
+                right: Box::new(self.unary()?),
+            }),
+            None => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> ParseResult<Expr> {
+        let mut e = self.primary()?;
+        loop {
+            if self.match_single(TokenKind::LeftParen).is_some() {
+                e = self.with_record_literals(true, |p| p.function_application_args(e))?;
+            } else if self.match_single(TokenKind::Dot).is_some() {
+                let name = self.expect(TokenKind::Identifier)?;
+                e = match self.match_single(TokenKind::LeftParen) {
+                    Some(_) => {
+                        let (args, paren) = self.with_record_literals(true, |p| p.arguments())?;
+                        Expr::MethodCall {
+                            span: e.span().to(paren.span),
+                            receiver: Box::new(e),
+                            method: name,
+                            args,
+                        }
+                    }
+                    None => Expr::FieldAccess {
+                        object: Box::new(e),
+                        name,
+                    },
+                };
+            } else {
+                return Ok(e);
+            }
         }
     }
 
     /// Assumes that we have already successfully parsed an opening paren,
     /// and proceeds to parse the argument list.
-    fn function_application_args(&mut self, callee: Expr) -> Option<Expr> {
+    fn function_application_args(&mut self, callee: Expr) -> ParseResult<Expr> {
+        let (args, paren) = self.arguments()?;
+        Ok(Expr::FunctionApplication {
+            span: callee.span().to(paren.span),
+            callee: Box::new(callee),
+            args,
+        })
+    }
+
+    /// Parses the arguments of a call up to & including the closing paren,
+    /// which is returned alongside them.
+    fn arguments(&mut self) -> ParseResult<(Vec<Expr>, Token)> {
         let mut args = Vec::new();
 
-        if !self.check_next(TokenKind::RightParen) {
-            args.push(self.expression()?);
-            while self.match_single(TokenKind::Comma).is_some() {
+        let paren = match self.match_single(TokenKind::RightParen) {
+            Some(paren) => paren,
+            None => {
                 args.push(self.expression()?);
+                while self.match_single(TokenKind::Comma).is_some() {
+                    args.push(self.expression()?);
+                }
+                self.match_single(TokenKind::RightParen)
+                    .ok_or_else(|| self.error(vec![TokenKind::Comma, TokenKind::RightParen]))?
             }
-        }
-
-        self.match_single(TokenKind::RightParen)?;
+        };
 
         // TODO: is a jvm style max arg limit necessary?
 
-        Some(Expr::FunctionApplication {
-            callee: Box::new(callee),
-            args,
+        Ok((args, paren))
+    }
+
+    /// Consumes the next token if it's a literal.
+    fn literal(&mut self) -> Option<(Literal, Span)> {
+        let l = match &self.tokens.peek()?.kind {
+            TokenKind::String(s) => Literal::new(s.as_str()),
+            TokenKind::Int(i) => Literal::new(*i),
+            TokenKind::True => Literal::new(true),
+            TokenKind::False => Literal::new(false),
+            _ => return None,
+        };
+        let token = self.advance().expect("Literal was peeked");
+        Some((l, token.span))
+    }
+
+    fn primary(&mut self) -> ParseResult<Expr> {
+        if let Some((l, span)) = self.literal() {
+            return Ok(Expr::Literal { l, span });
+        }
+        let kind = self.tokens.peek().map(|t| t.kind.clone());
+        match kind {
+            Some(TokenKind::Identifier) => {
+                let name = self.advance().expect("Identifier was peeked");
+                if self.record_literals_allowed && self.match_single(TokenKind::LeftBrace).is_some()
+                {
+                    self.record_literal(name)
+                } else {
+                    Ok(Expr::Identifier { name })
+                }
+            }
+            Some(TokenKind::LeftBrace | TokenKind::If | TokenKind::While | TokenKind::Match) => {
+                self.block_like()
+            }
+            Some(TokenKind::LeftParen) => {
+                let open = self.advance().expect("LeftParen was peeked");
+                self.with_record_literals(true, |p| p.grouping_or_tuple(open))
+            }
+            _ => Err(self.error(expression_start_kinds())),
+        }
+    }
+
+    /// Assumes that we have already parsed the opening parenthesis.
+    fn grouping_or_tuple(&mut self, open: Token) -> ParseResult<Expr> {
+        let first = self.expression()?;
+        if self.match_single(TokenKind::Comma).is_none() {
+            self.expect(TokenKind::RightParen)?;
+            return Ok(first);
+        }
+        let mut elements = vec![first];
+        elements.extend(self.comma_separated(TokenKind::RightParen, Self::expression)?);
+        Ok(Expr::Tuple {
+            elements,
+            span: open.span.to(self.previous_span),
         })
     }
 
-    fn primary(&mut self) -> Option<Expr> {
-        self.tokens.next().and_then(|t| match t.kind {
-            TokenKind::String(s) => Some(Expr::Literal { l: Literal::new(s) }),
-            TokenKind::Identifier => Some(Expr::Identifier { name: t }),
-            _ => None,
+    /// Assumes that we have already parsed the record's name & opening brace.
+    fn record_literal(&mut self, name: Token) -> ParseResult<Expr> {
+        let fields = self.comma_separated(TokenKind::RightBrace, |p| {
+            let name = p.expect(TokenKind::Identifier)?;
+            p.expect(TokenKind::Colon)?;
+            let value = p.expression()?;
+            Ok(FieldInit { name, value })
+        })?;
+        Ok(Expr::RecordLiteral {
+            span: name.span.to(self.previous_span),
+            name,
+            fields,
+        })
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let next = self.tokens.next();
+        if let Some(t) = &next {
+            self.previous_span = t.span;
+            self.previous_end = t.span.following(&t.lexeme);
+        }
+        next
+    }
+
+    fn expect(&mut self, t: TokenKind) -> ParseResult<Token> {
+        match self.match_single(t.clone()) {
+            Some(token) => Ok(token),
+            None => Err(self.error(vec![t])),
+        }
+    }
+
+    /// Describes the next token as being unexpected, without consuming it.
+    fn error(&mut self, expected: Vec<TokenKind>) -> Box<ParseError> {
+        let found = self.tokens.peek().cloned();
+        let span = match &found {
+            Some(t) => t.span,
+            None => self.previous_end,
+        };
+        Box::new(ParseError {
+            expected,
+            found,
+            span,
         })
     }
 
     fn match_single(&mut self, t: TokenKind) -> Option<Token> {
         if self.check_next(t) {
-            self.tokens.next()
+            self.advance()
         } else {
             None
         }
@@ -105,9 +960,100 @@ mod tests {
 
     #[test]
     fn empty_token_stream_returns_empty_ast() {
-        let ast = parse(token_stream(""));
+        let ast = parse(token_stream("")).unwrap();
         let expected = UntypedProgram { stmts: vec![] };
-        assert_eq!(expected, ast)
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test]
+    fn parses_every_statement_in_program() {
+        let ast = parse(token_stream("a;\n\"b\";\nc(d);")).unwrap();
+        let expected = UntypedProgram {
+            stmts: vec![
+                Stmt::Expr {
+                    e: Expr::identifier("a"),
+                },
+                Stmt::Expr { e: "b".into() },
+                Stmt::Expr {
+                    e: Expr::FunctionApplication {
+                        callee: Box::new(Expr::identifier("c")),
+                        args: vec![Expr::identifier("d")],
+                        span: Span::default(),
+                    },
+                },
+            ],
+        };
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input        , expected                         , found, line, column ]
+        [ "a; )"       , expression_start_kinds()         , ")"  , 1   , 4      ]
+        [ "a;\nb"      , vec![TokenKind::SemiColon]        , ""   , 2   , 2      ]
+        [ "a;\n  b(c;" , vec![TokenKind::Comma,
+                              TokenKind::RightParen]       , ";"  , 2   , 6      ]
+        [ "a(b c);"    , vec![TokenKind::Comma,
+                              TokenKind::RightParen]       , "c"  , 1   , 5      ]
+    )]
+    fn reports_unexpected_tokens(
+        input: &str,
+        expected: Vec<TokenKind>,
+        found: &str,
+        line: usize,
+        column: usize,
+    ) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(1, errs.len());
+
+        let err = &errs[0];
+        assert_eq!(expected, err.expected);
+        assert_eq!(found, err.found.as_ref().map_or("", |t| t.lexeme.as_str()));
+        assert_eq!((line, column), (err.span.line, err.span.column));
+    }
+
+    #[test_with_parameters(
+        [ input                        , error_locations          ]
+        [ "a b; c;"                    , vec![(1, 3)]             ]
+        [ "a b; c d;\ne;"              , vec![(1, 3), (1, 8)]     ]
+        [ "a b c d e; f"               , vec![(1, 3), (1, 13)]    ]
+        [ "a ) } b;"                   , vec![(1, 3)]             ]
+        [ "a b\nlet\nc d;"             , vec![(1, 3), (3, 3)]     ]
+        [ "a b\nmut c = ;"             , vec![(1, 3), (2, 9)]     ]
+        [ "a\nlet x = 1;"              , vec![(2, 1)]             ]
+        [ "a\nlet x: Int = 1; b c;"    , vec![(2, 1), (2, 19)]    ]
+        [ "a(;\nb(;\nc(;"              , vec![(1, 3), (2, 3), (3, 3)] ]
+    )]
+    fn recovers_at_statement_boundaries(input: &str, error_locations: Vec<(usize, usize)>) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        let locations: Vec<_> = errs.iter().map(|e| (e.span.line, e.span.column)).collect();
+        assert_eq!(error_locations, locations);
+    }
+
+    #[test]
+    fn describes_errors_for_humans() {
+        let errs = parse(token_stream("print_ln(\"a\" \"b\");")).unwrap_err();
+        assert_eq!(
+            "1:14: expected `,` or `)`, found `\"b\"`",
+            errs[0].to_string()
+        );
+
+        let errs = parse(token_stream("print_ln(")).unwrap_err();
+        assert_eq!(
+            "1:10: expected expression, found end of input",
+            errs[0].to_string()
+        );
+
+        let errs = parse(token_stream("print_ln(\"abc);")).unwrap_err();
+        assert_eq!("1:10: unterminated string literal", errs[0].to_string());
+
+        let errs = parse(token_stream("let s = \"a\nbc\"")).unwrap_err();
+        assert_eq!("2:4: expected `;`, found end of input", errs[0].to_string());
+
+        let errs = parse(token_stream("let s = \"über\"")).unwrap_err();
+        assert_eq!(
+            "1:15: expected `;`, found end of input",
+            errs[0].to_string()
+        );
     }
 
     #[test_with_parameters(
@@ -116,20 +1062,818 @@ mod tests {
         [ "\"Goodbye\";"      , "Goodbye"      ]
     )]
     fn single_string_literal_returns_string_expr_stmt(raw: &str, literal: &str) {
-        let ast = parse(token_stream(raw));
+        let ast = parse(token_stream(raw)).unwrap();
         let expected = UntypedProgram {
             stmts: vec![Stmt::Expr {
                 e: Expr::Literal {
                     l: Literal::new(literal),
+                    span: Span::default(),
                 },
             }],
         };
-        assert_eq!(expected, ast)
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input  , literal ]
+        [ "0;"   , 0       ]
+        [ "500;" , 500     ]
+    )]
+    fn single_int_literal_returns_int_expr_stmt(raw: &str, literal: i64) {
+        let ast = parse(token_stream(raw)).unwrap();
+        let expected = UntypedProgram {
+            stmts: vec![Stmt::Expr { e: literal.into() }],
+        };
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input    , literal ]
+        [ "true;"  , true    ]
+        [ "false;" , false   ]
+    )]
+    fn single_bool_literal_returns_bool_expr_stmt(raw: &str, literal: bool) {
+        let ast = parse(token_stream(raw)).unwrap();
+        let expected = UntypedProgram {
+            stmts: vec![Stmt::Expr { e: literal.into() }],
+        };
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input            , expected                                                         ]
+        [ "1 + 2;"         , util::binary(1.into(), "+", 2.into())                            ]
+        [ "1 - 2 - 3;"     , util::binary(util::binary(1.into(), "-", 2.into()), "-", 3.into()) ]
+        [ "1 + 2 * 3;"     , util::binary(1.into(), "+", util::binary(2.into(), "*", 3.into())) ]
+        [ "(1 + 2) * 3;"   , util::binary(util::binary(1.into(), "+", 2.into()), "*", 3.into()) ]
+        [ "1 % 2 / 3;"     , util::binary(util::binary(1.into(), "%", 2.into()), "/", 3.into()) ]
+        [ "-1 < 2 == true;", util::binary(
+                                 util::binary(util::unary("-", 1.into()), "<", 2.into()),
+                                 "==",
+                                 true.into())                                                 ]
+        [ "a || b && !c;"  , util::binary(
+                                 Expr::identifier("a"),
+                                 "||",
+                                 util::binary(
+                                     Expr::identifier("b"),
+                                     "&&",
+                                     util::unary("!", Expr::identifier("c"))))               ]
+        [ "1 >= 2 != 3 <= 4;", util::binary(
+                                 util::binary(1.into(), ">=", 2.into()),
+                                 "!=",
+                                 util::binary(3.into(), "<=", 4.into()))                      ]
+        [ "!!f(1) > 0;"    , util::binary(
+                                 util::unary("!", util::unary("!", util::call("f", vec![1.into()]))),
+                                 ">",
+                                 0.into())                                                    ]
+    )]
+    fn operators_respect_precedence(input: &str, expected: Expr) {
+        let ast = parse(token_stream(input)).unwrap();
+        let expected = UntypedProgram {
+            stmts: vec![Stmt::Expr { e: expected }],
+        };
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test]
+    fn binary_expression_spans_both_operands() {
+        let ast = parse(token_stream("1 + 2 * 300;")).unwrap();
+        let span = match &ast.stmts[..] {
+            [Stmt::Expr { e }] => e.span(),
+            _ => panic!("Expected a single expression statement, got {:?}", ast),
+        };
+        assert_eq!((0, 11), (span.start, span.end));
+    }
+
+    #[test_with_parameters(
+        [ input                    , expected ]
+        [ "let x = 1;"             , Stmt::Let {
+                                         name: Token::identifier("x"),
+                                         ty: None,
+                                         initializer: 1.into(),
+                                     }                                                ]
+        [ "let s: String = \"a\";" , Stmt::Let {
+                                         name: Token::identifier("s"),
+                                         ty: Some(TypeExpr::named("String")),
+                                         initializer: "a".into(),
+                                     }                                                ]
+        [ "mut y = 1 + 2;"         , Stmt::Mut {
+                                         name: Token::identifier("y"),
+                                         ty: None,
+                                         initializer: util::binary(1.into(), "+", 2.into()),
+                                     }                                                ]
+        [ "mut b: Bool = true;"    , Stmt::Mut {
+                                         name: Token::identifier("b"),
+                                         ty: Some(TypeExpr::named("Bool")),
+                                         initializer: true.into(),
+                                     }                                                ]
+    )]
+    fn bindings(input: &str, expected: Stmt) {
+        let ast = parse(token_stream(input)).unwrap();
+        util::assert_same_syntax(UntypedProgram::with_stmts(vec![expected]), ast)
+    }
+
+    #[test]
+    fn fn_declaration() {
+        let ast = parse(token_stream(
+            "fn add(x: Int, y: Int) -> Int {\n    let z = x;\n    z + y\n}",
+        ))
+        .unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Fn {
+            decl: FnDecl {
+                name: Token::identifier("add"),
+                params: vec![
+                    Param {
+                        name: Token::identifier("x"),
+                        ty: TypeExpr::named("Int"),
+                    },
+                    Param {
+                        name: Token::identifier("y"),
+                        ty: TypeExpr::named("Int"),
+                    },
+                ],
+                return_type: Some(TypeExpr::named("Int")),
+                body: Block {
+                    stmts: vec![Stmt::Let {
+                        name: Token::identifier("z"),
+                        ty: None,
+                        initializer: Expr::identifier("x"),
+                    }],
+                    tail: Some(Box::new(util::binary(
+                        Expr::identifier("z"),
+                        "+",
+                        Expr::identifier("y"),
+                    ))),
+                    span: Span::default(),
+                },
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input                         , params, return_type, stmts, has_tail ]
+        [ "fn f() {}"                   , 0     , false      , 0    , false    ]
+        [ "fn f(a: Int,) { a; }"        , 1     , false      , 1    , false    ]
+        [ "fn f() -> Int { fn g() {} 1 }", 0    , true       , 1    , true     ]
+        [ "fn f(a: A, b: B) { a; b }"   , 2     , false      , 1    , true     ]
+    )]
+    fn fn_declaration_shapes(
+        input: &str,
+        params: usize,
+        return_type: bool,
+        stmts: usize,
+        has_tail: bool,
+    ) {
+        let ast = parse(token_stream(input)).unwrap();
+        match &ast.stmts[..] {
+            [Stmt::Fn { decl }] => {
+                assert_eq!(params, decl.params.len());
+                assert_eq!(return_type, decl.return_type.is_some());
+                assert_eq!(stmts, decl.body.stmts.len());
+                assert_eq!(has_tail, decl.body.tail.is_some());
+            }
+            _ => panic!("Expected a single fn declaration, got {:?}", ast),
+        }
+    }
+
+    #[test_with_parameters(
+        [ input                  , message                                         ]
+        [ "fn (a: Int) {}"       , "1:4: expected identifier, found `(`"           ]
+        [ "fn f(a) {}"           , "1:7: expected `:`, found `)`"                  ]
+        [ "fn f(a: Int b) {}"    , "1:13: expected `,` or `)`, found `b`"          ]
+        [ "fn f() Int {}"        , "1:8: expected `->` or `{`, found `Int`"        ]
+        [ "fn f() -> Int;"       , "1:14: expected `{`, found `;`"                 ]
+        [ "fn f() { a b }"       , "1:12: expected `;` or `}`, found `b`"          ]
+        [ "fn f() { a; "         , "1:12: expected expression, found end of input" ]
+    )]
+    fn malformed_fn_declarations(input: &str, message: &str) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(message, errs[0].to_string());
+    }
+
+    #[test]
+    fn block_expressions() {
+        let ast = parse(token_stream("let x = { let y = 1; y } + 1;")).unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Let {
+            name: Token::identifier("x"),
+            ty: None,
+            initializer: util::binary(
+                Expr::Block {
+                    block: Block {
+                        stmts: vec![Stmt::Let {
+                            name: Token::identifier("y"),
+                            ty: None,
+                            initializer: 1.into(),
+                        }],
+                        tail: Some(Box::new(Expr::identifier("y"))),
+                        span: Span::default(),
+                    },
+                },
+                "+",
+                1.into(),
+            ),
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input                   , stmts, has_tail ]
+        [ "fn f() { {} }"         , 0    , true     ]
+        [ "fn f() { {}; }"        , 1    , false    ]
+        [ "fn f() { {} {} 1 }"    , 2    , true     ]
+        [ "fn f() { { 1 } - 1 }"  , 1    , true     ]
+    )]
+    fn blocks_end_statements_without_semicolons(input: &str, stmts: usize, has_tail: bool) {
+        let ast = parse(token_stream(input)).unwrap();
+        match &ast.stmts[..] {
+            [Stmt::Fn { decl }] => {
+                assert_eq!(stmts, decl.body.stmts.len());
+                assert_eq!(has_tail, decl.body.tail.is_some());
+            }
+            _ => panic!("Expected a single fn declaration, got {:?}", ast),
+        }
+    }
+
+    #[test]
+    fn if_else_chains() {
+        let ast = parse(token_stream("if a { 1 } else if b { 2 } else { 3 }")).unwrap();
+        let block = |e: Expr| Block {
+            stmts: vec![],
+            tail: Some(Box::new(e)),
+            span: Span::default(),
+        };
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Expr {
+            e: Expr::If {
+                keyword: util::token("if"),
+                condition: Box::new(Expr::identifier("a")),
+                then_branch: block(1.into()),
+                else_branch: Some(Box::new(Expr::If {
+                    keyword: util::token("if"),
+                    condition: Box::new(Expr::identifier("b")),
+                    then_branch: block(2.into()),
+                    else_branch: Some(Box::new(Expr::Block {
+                        block: block(3.into()),
+                    })),
+                })),
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test]
+    fn while_loops() {
+        let ast = parse(token_stream("while i < 3 { i = i + 1; }")).unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Expr {
+            e: Expr::While {
+                keyword: util::token("while"),
+                condition: Box::new(util::binary(Expr::identifier("i"), "<", 3.into())),
+                body: Block {
+                    stmts: vec![Stmt::Expr {
+                        e: Expr::Assign {
+                            name: Token::identifier("i"),
+                            value: Box::new(util::binary(Expr::identifier("i"), "+", 1.into())),
+                        },
+                    }],
+                    tail: None,
+                    span: Span::default(),
+                },
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input                                         , stmts, has_tail ]
+        [ "fn f() { if a { b; } if c { d } }"           , 1    , true     ]
+        [ "fn f() { while a { } let x = if b { 1 } else { 2 }; x }", 2, true ]
+        [ "fn f() { if a { 1 } else { 2 }; }"           , 1    , false    ]
+    )]
+    fn if_and_while_are_block_like(input: &str, stmts: usize, has_tail: bool) {
+        let ast = parse(token_stream(input)).unwrap();
+        match &ast.stmts[..] {
+            [Stmt::Fn { decl }] => {
+                assert_eq!(stmts, decl.body.stmts.len());
+                assert_eq!(has_tail, decl.body.tail.is_some());
+            }
+            _ => panic!("Expected a single fn declaration, got {:?}", ast),
+        }
+    }
+
+    #[test_with_parameters(
+        [ input                   , message                                       ]
+        [ "if a b"                , "1:6: expected `{`, found `b`"                ]
+        [ "if a {} else b"        , "1:14: expected `if` or `{`, found `b`"       ]
+        [ "while {}"              , "1:9: expected `{`, found end of input"       ]
+    )]
+    fn malformed_conditionals(input: &str, message: &str) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(message, errs[0].to_string());
+    }
+
+    #[test]
+    fn record_declaration() {
+        let ast = parse(token_stream(
+            "record Person {\n  name: String,\n  age: Int,\n}",
+        ))
+        .unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Record {
+            decl: RecordDecl {
+                name: Token::identifier("Person"),
+                fields: vec![
+                    Field {
+                        name: Token::identifier("name"),
+                        ty: TypeExpr::named("String"),
+                    },
+                    Field {
+                        name: Token::identifier("age"),
+                        ty: TypeExpr::named("Int"),
+                    },
+                ],
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test]
+    fn refinement_types() {
+        let ast = parse(token_stream(
+            "record Person {
+                name: String checking NonEmpty,
+                age: Int checking StrictlyBetween(0, 130) checking Even(),
+            }",
+        ))
+        .unwrap();
+        let checking = |base: TypeExpr, check: &str, args: Vec<Expr>| TypeExpr::Checking {
+            base: Box::new(base),
+            check: Token::identifier(check),
+            args,
+            span: Span::default(),
+        };
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Record {
+            decl: RecordDecl {
+                name: Token::identifier("Person"),
+                fields: vec![
+                    Field {
+                        name: Token::identifier("name"),
+                        ty: checking(TypeExpr::named("String"), "NonEmpty", vec![]),
+                    },
+                    Field {
+                        name: Token::identifier("age"),
+                        ty: checking(
+                            checking(
+                                TypeExpr::named("Int"),
+                                "StrictlyBetween",
+                                vec![Expr::from(0), Expr::from(130)],
+                            ),
+                            "Even",
+                            vec![],
+                        ),
+                    },
+                ],
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input                                , message                                              ]
+        [ "let x: Int checking = 1;"           , "1:21: expected identifier, found `=`"               ]
+        [ "fn f(x: Int checking R(1) {}"       , "1:27: expected `,` or `)`, found `{`"               ]
+        [ "let x: Int checking R(1 = 1;"       , "1:25: expected `,` or `)`, found `=`"               ]
+        [ "let x: between(0, 1) = 1;"          , "1:22: expected identifier, found `=`"               ]
+    )]
+    fn malformed_refinement_types(input: &str, message: &str) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(message, errs[0].to_string());
+    }
+
+    #[test_with_parameters(
+        [ prefixed                             , postfix                                    ]
+        [ "non_zero Int"                       , "Int checking non_zero"                    ]
+        [ "between(0, 130) Int"                , "Int checking between(0, 130)"             ]
+        [ "even positive Int"                  , "Int checking positive checking even"      ]
+        [ "positive Int checking Even"         , "Int checking positive checking Even"      ]
+    )]
+    fn prefixed_refinement_types(prefixed: &str, postfix: &str) {
+        let parse_type = |ty: &str| parse(token_stream(&format!("let x: {} = 1;", ty))).unwrap();
+        util::assert_same_syntax(parse_type(postfix), parse_type(prefixed))
+    }
+
+    #[test]
+    fn check_declaration() {
+        let ast = parse(token_stream(
+            "check between<Int>(lower: Int, upper: Int) { lower < value && value < upper }",
+        ))
+        .unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Check {
+            decl: CheckDecl {
+                name: Token::identifier("between"),
+                subject: TypeExpr::named("Int"),
+                params: vec![
+                    Param {
+                        name: Token::identifier("lower"),
+                        ty: TypeExpr::named("Int"),
+                    },
+                    Param {
+                        name: Token::identifier("upper"),
+                        ty: TypeExpr::named("Int"),
+                    },
+                ],
+                body: Block {
+                    stmts: vec![],
+                    tail: Some(Box::new(util::binary(
+                        util::binary(Expr::identifier("lower"), "<", Expr::identifier("value")),
+                        "&&",
+                        util::binary(Expr::identifier("value"), "<", Expr::identifier("upper")),
+                    ))),
+                    span: Span::default(),
+                },
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input                                , message                                              ]
+        [ "check c { okay }"                   , "1:9: expected `<`, found `{`"                       ]
+        [ "check c<Int { okay }"               , "1:13: expected `>`, found `{`"                      ]
+        [ "check c<Int> okay"                  , "1:14: expected `(` or `{`, found `okay`"            ]
+        [ "check c<Int>() okay"                , "1:16: expected `{`, found `okay`"                   ]
+    )]
+    fn malformed_check_declarations(input: &str, message: &str) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(message, errs[0].to_string());
+    }
+
+    #[test]
+    fn enum_declaration() {
+        let src = "enum CoffeeMaker {
+            aeropress(inverted: Bool),
+            espresso,
+            pour_over(brand: String, cups: Int,),
+        }";
+        let ast = parse(token_stream(src)).unwrap();
+        let field = |name: &str, ty: &str| Field {
+            name: Token::identifier(name),
+            ty: TypeExpr::named(ty),
+        };
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Enum {
+            decl: EnumDecl {
+                name: Token::identifier("CoffeeMaker"),
+                variants: vec![
+                    Variant {
+                        name: Token::identifier("aeropress"),
+                        fields: vec![field("inverted", "Bool")],
+                    },
+                    Variant {
+                        name: Token::identifier("espresso"),
+                        fields: vec![],
+                    },
+                    Variant {
+                        name: Token::identifier("pour_over"),
+                        fields: vec![field("brand", "String"), field("cups", "Int")],
+                    },
+                ],
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test]
+    fn record_literals_and_field_access() {
+        let ast = parse(token_stream("Person { name: \"Matthew\", age: 29 }.age;")).unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Expr {
+            e: Expr::FieldAccess {
+                object: Box::new(Expr::RecordLiteral {
+                    name: Token::identifier("Person"),
+                    fields: vec![
+                        FieldInit {
+                            name: Token::identifier("name"),
+                            value: "Matthew".into(),
+                        },
+                        FieldInit {
+                            name: Token::identifier("age"),
+                            value: 29.into(),
+                        },
+                    ],
+                    span: Span::default(),
+                }),
+                name: Token::identifier("age"),
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input                                      , expected                                  ]
+        [ "a.b.c;"                                   , util::field(util::field(Expr::identifier("a"), "b"), "c") ]
+        [ "f(a).b;"                                  , util::field(util::call("f", vec![Expr::identifier("a")]), "b") ]
+        [ "-a.b;"                                    , util::unary("-", util::field(Expr::identifier("a"), "b")) ]
+    )]
+    fn field_access_is_postfix(input: &str, expected: Expr) {
+        let ast = parse(token_stream(input)).unwrap();
+        util::assert_same_syntax(
+            UntypedProgram::with_stmts(vec![Stmt::Expr { e: expected }]),
+            ast,
+        )
+    }
+
+    #[test_with_parameters(
+        [ input                                          , parses ]
+        [ "if a { }"                                     , true   ]
+        [ "if A { a: 1 } { }"                            , false  ]
+        [ "if a == (A {}) { }"                           , true   ]
+        [ "if f(A {}) { }"                               , true   ]
+        [ "while a { A {}; }"                            , true   ]
+    )]
+    fn record_literals_are_not_allowed_in_conditions(input: &str, parses: bool) {
+        assert_eq!(parses, parse(token_stream(input)).is_ok());
+    }
+
+    #[test_with_parameters(
+        [ input                         , message                                   ]
+        [ "record { }"                  , "1:8: expected identifier, found `{`"     ]
+        [ "record A { a }"              , "1:14: expected `:`, found `}`"           ]
+        [ "record A { a: Int b: Int }"  , "1:19: expected `,` or `}`, found `b`"    ]
+        [ "A { a 1 };"                  , "1:7: expected `:`, found `1`"            ]
+        [ "a.1;"                        , "1:3: expected identifier, found `1`"     ]
+        [ "enum E { a b }"              , "1:12: expected `,` or `}`, found `b`"    ]
+        [ "enum E { a(Int) }"           , "1:15: expected `:`, found `)`"           ]
+        [ "enum E { a(x: Int }"         , "1:19: expected `,` or `)`, found `}`"    ]
+    )]
+    fn malformed_records_and_enums(input: &str, message: &str) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(message, errs[0].to_string());
+    }
+
+    #[test]
+    fn impl_declaration() {
+        let src = "impl Person {
+            fn is_adult() -> Bool { self.age > 18 }
+            fn greet(greeting: String) {}
+        }";
+        let ast = parse(token_stream(src)).unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Impl {
+            decl: ImplDecl {
+                type_name: Token::identifier("Person"),
+                interface: None,
+                methods: vec![
+                    FnDecl {
+                        name: Token::identifier("is_adult"),
+                        params: vec![],
+                        return_type: Some(TypeExpr::named("Bool")),
+                        body: Block {
+                            stmts: vec![],
+                            tail: Some(Box::new(util::binary(
+                                util::field(Expr::identifier("self"), "age"),
+                                ">",
+                                18.into(),
+                            ))),
+                            span: Span::default(),
+                        },
+                    },
+                    FnDecl {
+                        name: Token::identifier("greet"),
+                        params: vec![Param {
+                            name: Token::identifier("greeting"),
+                            ty: TypeExpr::named("String"),
+                        }],
+                        return_type: None,
+                        body: Block {
+                            stmts: vec![],
+                            tail: None,
+                            span: Span::default(),
+                        },
+                    },
+                ],
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test]
+    fn interface_declaration_and_conformance() {
+        let src = "interface Describable {
+            fn description() -> String
+            fn describe_to(other: String)
+        }
+        impl CoffeeMaker: Describable {}";
+        let ast = parse(token_stream(src)).unwrap();
+        let expected = UntypedProgram::with_stmts(vec![
+            Stmt::Interface {
+                decl: InterfaceDecl {
+                    name: Token::identifier("Describable"),
+                    methods: vec![
+                        FnSignature {
+                            name: Token::identifier("description"),
+                            params: vec![],
+                            return_type: Some(TypeExpr::named("String")),
+                        },
+                        FnSignature {
+                            name: Token::identifier("describe_to"),
+                            params: vec![Param {
+                                name: Token::identifier("other"),
+                                ty: TypeExpr::named("String"),
+                            }],
+                            return_type: None,
+                        },
+                    ],
+                },
+            },
+            Stmt::Impl {
+                decl: ImplDecl {
+                    type_name: Token::identifier("CoffeeMaker"),
+                    interface: Some(Token::identifier("Describable")),
+                    methods: vec![],
+                },
+            },
+        ]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input                         , expected ]
+        [ "a.b();"                      , util::method_call(Expr::identifier("a"), "b", vec![]) ]
+        [ "\"a\".appending(b, c);"      , util::method_call("a".into(), "appending", vec![Expr::identifier("b"), Expr::identifier("c")]) ]
+        [ "a.b().c;"                    , util::field(util::method_call(Expr::identifier("a"), "b", vec![]), "c") ]
+        [ "a.b.c(d)(e);"                , util::call_expr(util::method_call(util::field(Expr::identifier("a"), "b"), "c", vec![Expr::identifier("d")]), vec![Expr::identifier("e")]) ]
+    )]
+    fn method_calls_are_postfix(input: &str, expected: Expr) {
+        let ast = parse(token_stream(input)).unwrap();
+        util::assert_same_syntax(
+            UntypedProgram::with_stmts(vec![Stmt::Expr { e: expected }]),
+            ast,
+        )
+    }
+
+    #[test_with_parameters(
+        [ input                      , message                                   ]
+        [ "impl { }"                 , "1:6: expected identifier, found `{`"     ]
+        [ "impl A { let x = 1; }"    , "1:10: expected `fn` or `}`, found `let`" ]
+        [ "impl A { fn f() {}"       , "1:19: expected `fn` or `}`, found end of input" ]
+        [ "impl A B { }"             , "1:8: expected `:` or `{`, found `B`"     ]
+        [ "impl A: { }"              , "1:9: expected identifier, found `{`"     ]
+        [ "impl A: B fn"             , "1:11: expected `{`, found `fn`"          ]
+        [ "interface I { fn f() {} }", "1:22: expected `fn` or `}`, found `{`"   ]
+        [ "interface I { let x; }"   , "1:15: expected `fn` or `}`, found `let`" ]
+        [ "a.b(c;"                   , "1:6: expected `,` or `)`, found `;`"     ]
+    )]
+    fn malformed_impls_and_interfaces(input: &str, message: &str) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(message, errs[0].to_string());
+    }
+
+    #[test]
+    fn match_expressions() {
+        let src = "match maker {
+            kalita_wave | v60 => true,
+            _ => false,
+        }";
+        let ast = parse(token_stream(src)).unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Expr {
+            e: Expr::Match {
+                scrutinee: Box::new(Expr::identifier("maker")),
+                arms: vec![
+                    MatchArm {
+                        pattern: Pattern::Or {
+                            alternatives: vec![
+                                Pattern::identifier("kalita_wave"),
+                                Pattern::identifier("v60"),
+                            ],
+                        },
+                        body: true.into(),
+                    },
+                    MatchArm {
+                        pattern: Pattern::Wildcard {
+                            span: Span::default(),
+                        },
+                        body: false.into(),
+                    },
+                ],
+                span: Span::default(),
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ pattern                  , expected ]
+        [ "x"                      , Pattern::identifier("x") ]
+        [ "\"a\""                  , util::literal_pattern("a") ]
+        [ "-1"                     , util::literal_pattern(-1) ]
+        [ "true"                   , util::literal_pattern(true) ]
+        [ "(a, _)"                 , util::tuple_pattern(vec![Pattern::identifier("a"), util::wildcard()]) ]
+        [ "a, _"                   , util::tuple_pattern(vec![Pattern::identifier("a"), util::wildcard()]) ]
+        [ "(a)"                    , Pattern::identifier("a") ]
+        [ "succ(n)"                , util::variant_pattern(None, "succ", Some(vec![Pattern::identifier("n")])) ]
+        [ "E.a"                    , util::variant_pattern(Some("E"), "a", None) ]
+        [ "E.a(1 | 2, _,)"         , util::variant_pattern(Some("E"), "a", Some(vec![
+                                         Pattern::Or { alternatives: vec![util::literal_pattern(1), util::literal_pattern(2)] },
+                                         util::wildcard(),
+                                     ])) ]
+        [ "a | b, c"               , util::tuple_pattern(vec![
+                                         Pattern::Or { alternatives: vec![Pattern::identifier("a"), Pattern::identifier("b")] },
+                                         Pattern::identifier("c"),
+                                     ]) ]
+    )]
+    fn patterns(pattern: &str, expected: Pattern) {
+        let ast = parse(token_stream(&format!("match x {{ {} => 0 }}", pattern))).unwrap();
+        match &ast.stmts[..] {
+            [Stmt::Expr {
+                e: Expr::Match { arms, .. },
+            }] => util::assert_same_syntax(&expected, &arms[0].pattern),
+            _ => panic!("Expected a single match expression, got {:?}", ast),
+        }
+    }
+
+    #[test_with_parameters(
+        [ input                                      , arms ]
+        [ "match x { }"                              , 0    ]
+        [ "match x { 1 => a, 2 => b }"               , 2    ]
+        [ "match x { 1 => { a } 2 => if b { c } }"   , 2    ]
+        [ "match X { a: 1 } { }"                     , 0    ]
+    )]
+    fn match_arms_are_comma_separated(input: &str, arms: usize) {
+        let result = parse(token_stream(input));
+        match result.as_ref().map(|ast| &ast.stmts[..]) {
+            Ok(
+                [Stmt::Expr {
+                    e: Expr::Match { arms: parsed, .. },
+                }],
+            ) => assert_eq!(arms, parsed.len()),
+            _ => assert_eq!(0, arms, "Expected a single match, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn tuple_expressions() {
+        let ast = parse(token_stream("(a, 1,); (a);")).unwrap();
+        let expected = UntypedProgram::with_stmts(vec![
+            Stmt::Expr {
+                e: Expr::Tuple {
+                    elements: vec![Expr::identifier("a"), 1.into()],
+                    span: Span::default(),
+                },
+            },
+            Stmt::Expr {
+                e: Expr::identifier("a"),
+            },
+        ]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input                          , message                                  ]
+        [ "match x { 1 => a 2 => b }"    , "1:18: expected `,` or `}`, found `2`"   ]
+        [ "match x { 1 a }"              , "1:13: expected `=>`, found `a`"         ]
+        [ "match x { => a }"             , "1:11: expected pattern, found `=>`"     ]
+        [ "match x { - a => 1 }"         , "1:13: expected integer literal, found `a`" ]
+        [ "match x { E. => 1 }"          , "1:14: expected identifier, found `=>`"  ]
+        [ "match x { a | => 1 }"         , "1:15: expected pattern, found `=>`"     ]
+    )]
+    fn malformed_matches(input: &str, message: &str) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(message, errs[0].to_string());
+    }
+
+    #[test]
+    fn top_level_blocks_are_statements() {
+        let ast = parse(token_stream("{ a; } { b }; c;")).unwrap();
+        assert_eq!(3, ast.stmts.len());
+    }
+
+    #[test]
+    fn assignment_is_right_associative() {
+        let ast = parse(token_stream("a = b = 1 + 2;")).unwrap();
+        let expected = UntypedProgram::with_stmts(vec![Stmt::Expr {
+            e: Expr::Assign {
+                name: Token::identifier("a"),
+                value: Box::new(Expr::Assign {
+                    name: Token::identifier("b"),
+                    value: Box::new(util::binary(1.into(), "+", 2.into())),
+                }),
+            },
+        }]);
+        util::assert_same_syntax(expected, ast)
+    }
+
+    #[test_with_parameters(
+        [ input          , message                                       ]
+        [ "let = 1;"     , "1:5: expected identifier, found `=`"         ]
+        [ "let x 1;"     , "1:7: expected `:` or `=`, found `1`"         ]
+        [ "mut x: Int;"  , "1:11: expected `=`, found `;`"               ]
+        [ "let x = 1"    , "1:10: expected `;`, found end of input"      ]
+        [ "1 + a = 2;"   , "1:7: expected `;`, found `=`"                ]
+    )]
+    fn malformed_bindings_and_assignments(input: &str, message: &str) {
+        let errs = parse(token_stream(input)).unwrap_err();
+        assert_eq!(message, errs[0].to_string());
     }
 
     #[test]
     fn raw_identifier() {
-        let ast = parse(token_stream("some_ident;"));
+        let ast = parse(token_stream("some_ident;")).unwrap();
         let expected = UntypedProgram {
             stmts: vec![Stmt::Expr {
                 e: Expr::Identifier {
@@ -137,7 +1881,7 @@ mod tests {
                 },
             }],
         };
-        assert_eq!(expected, ast)
+        util::assert_same_syntax(expected, ast)
     }
 
     #[test_with_parameters(
@@ -147,7 +1891,7 @@ mod tests {
         [ "a(\"b\", \"c\");" , "a"           , vec!["b".into(), "c".into()] ]
     )]
     fn function_application(input: &str, identifier: &str, args: Vec<Expr>) {
-        let ast = parse(token_stream(input));
+        let ast = parse(token_stream(input)).unwrap();
         let expected = UntypedProgram {
             stmts: vec![Stmt::Expr {
                 e: Expr::FunctionApplication {
@@ -155,9 +1899,123 @@ mod tests {
                         name: Token::identifier(identifier),
                     }),
                     args,
+                    span: Span::default(),
                 },
             }],
         };
-        assert_eq!(expected, ast)
+        util::assert_same_syntax(expected, ast)
+    }
+
+    mod util {
+        use super::super::*;
+        use lexer::token_stream;
+
+        /// Asserts that two pieces of syntax are the same apart from where
+        /// they're written, as expectations are mostly built without spans.
+        pub(crate) fn assert_same_syntax<T: fmt::Debug>(expected: T, actual: T) {
+            assert_eq!(
+                without_spans(&format!("{:#?}", expected)),
+                without_spans(&format!("{:#?}", actual))
+            )
+        }
+
+        /// Blanks out the positions of every span in some syntax's `Debug`
+        /// output.
+        fn without_spans(debug: &str) -> String {
+            let mut result = String::new();
+            let mut rest = debug;
+            while let Some(start) = rest.find("Span {") {
+                result.push_str(&rest[..start + "Span".len()]);
+                let end = rest[start..].find('}').expect("Spans are closed");
+                rest = &rest[start + end + 1..];
+            }
+            result.push_str(rest);
+            result
+        }
+
+        /// Lexes a single operator or keyword.
+        pub(crate) fn token(lexeme: &str) -> Token {
+            token_stream(lexeme).next().expect("No token lexed")
+        }
+
+        pub(crate) fn binary(left: Expr, op: &str, right: Expr) -> Expr {
+            Expr::Binary {
+                left: Box::new(left),
+                op: token(op),
+                right: Box::new(right),
+            }
+        }
+
+        pub(crate) fn unary(op: &str, right: Expr) -> Expr {
+            Expr::Unary {
+                op: token(op),
+                right: Box::new(right),
+            }
+        }
+
+        pub(crate) fn wildcard() -> Pattern {
+            Pattern::Wildcard {
+                span: Span::default(),
+            }
+        }
+
+        pub(crate) fn literal_pattern<L: Into<Literal>>(l: L) -> Pattern {
+            Pattern::Literal {
+                l: l.into(),
+                span: Span::default(),
+            }
+        }
+
+        pub(crate) fn tuple_pattern(elements: Vec<Pattern>) -> Pattern {
+            Pattern::Tuple {
+                elements,
+                span: Span::default(),
+            }
+        }
+
+        pub(crate) fn variant_pattern(
+            enum_name: Option<&str>,
+            name: &str,
+            fields: Option<Vec<Pattern>>,
+        ) -> Pattern {
+            Pattern::Variant {
+                enum_name: enum_name.map(Token::identifier),
+                name: Token::identifier(name),
+                fields,
+                span: Span::default(),
+            }
+        }
+
+        pub(crate) fn method_call(receiver: Expr, method: &str, args: Vec<Expr>) -> Expr {
+            Expr::MethodCall {
+                receiver: Box::new(receiver),
+                method: Token::identifier(method),
+                args,
+                span: Span::default(),
+            }
+        }
+
+        pub(crate) fn call_expr(callee: Expr, args: Vec<Expr>) -> Expr {
+            Expr::FunctionApplication {
+                callee: Box::new(callee),
+                args,
+                span: Span::default(),
+            }
+        }
+
+        pub(crate) fn field(object: Expr, name: &str) -> Expr {
+            Expr::FieldAccess {
+                object: Box::new(object),
+                name: Token::identifier(name),
+            }
+        }
+
+        pub(crate) fn call(callee: &str, args: Vec<Expr>) -> Expr {
+            Expr::FunctionApplication {
+                callee: Box::new(Expr::identifier(callee)),
+                args,
+                span: Span::default(),
+            }
+        }
     }
 }
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum TokenKind {
     LeftBrace,    // {
@@ -14,6 +16,7 @@ pub enum TokenKind {
     Minus,        // -
     Star,         // *
     Slash,        // /
+    Percent,      // %
     Equal,        // =
     EqualEqual,   // ==
     Greater,      // >
@@ -21,8 +24,10 @@ pub enum TokenKind {
     LessEqual,    // <=
     GreaterEqual, // >=
     RightArrow,   // ->
+    FatArrow,     // =>
     And,          // &&
     Or,           // ||
+    Pipe,         // |
 
     Let,       // let
     Mut,       // mut
@@ -32,20 +37,179 @@ pub enum TokenKind {
     Interface, // interface
     Impl,      // impl
     Check,     // check
+    Checking,  // checking
     Match,     // match
+    True,      // true
+    False,     // false
+    If,        // if
+    Else,      // else
+    While,     // while
 
     Identifier, // [_a-zA-Z][_a-zA-Z0-9]*
 
     String(String), // \".*\"
+    Int(i64),       // [0-9]+
 
     Unknown, // anything else
+
+    Error(LexError), // malformed source which can't form a valid token
+}
+
+/// Describes why some source couldn't be lexed into a valid token.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LexError {
+    /// A string literal whose closing quote is missing. The token's span
+    /// begins at the opening quote.
+    UnterminatedString,
+    /// A string literal containing a backslash which doesn't begin one of
+    /// the supported escape sequences.
+    InvalidEscape(String),
+    /// An integer literal which is too large to be represented.
+    IntegerOutOfRange,
+    /// An integer literal immediately followed by letters or underscores,
+    /// as in `12ab`.
+    InvalidInteger(String),
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexError::UnterminatedString => write!(f, "unterminated string literal"),
+            LexError::InvalidEscape(sequence) => {
+                write!(
+                    f,
+                    "invalid escape sequence `{}` in string literal",
+                    sequence
+                )
+            }
+            LexError::IntegerOutOfRange => write!(f, "integer literal is too large"),
+            LexError::InvalidInteger(literal) => {
+                write!(f, "invalid integer literal `{}`", literal)
+            }
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TokenKind::*;
+
+        let description = match self {
+            LeftBrace => "`{`",
+            RightBrace => "`}`",
+            LeftParen => "`(`",
+            RightParen => "`)`",
+            Dot => "`.`",
+            Comma => "`,`",
+            Colon => "`:`",
+            SemiColon => "`;`",
+            Bang => "`!`",
+            BangEqual => "`!=`",
+            Plus => "`+`",
+            Minus => "`-`",
+            Star => "`*`",
+            Slash => "`/`",
+            Percent => "`%`",
+            Equal => "`=`",
+            EqualEqual => "`==`",
+            Greater => "`>`",
+            Less => "`<`",
+            LessEqual => "`<=`",
+            GreaterEqual => "`>=`",
+            RightArrow => "`->`",
+            FatArrow => "`=>`",
+            And => "`&&`",
+            Or => "`||`",
+            Pipe => "`|`",
+            Let => "`let`",
+            Mut => "`mut`",
+            Fn => "`fn`",
+            Enum => "`enum`",
+            Record => "`record`",
+            Interface => "`interface`",
+            Impl => "`impl`",
+            Check => "`check`",
+            Checking => "`checking`",
+            Match => "`match`",
+            True => "`true`",
+            False => "`false`",
+            If => "`if`",
+            Else => "`else`",
+            While => "`while`",
+            Identifier => "identifier",
+            String(_) => "string literal",
+            Int(_) => "integer literal",
+            Unknown => "unknown character",
+            Error(_) => "invalid token",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// The region of source text from which a token (or any larger piece of
+/// syntax) was produced.
+///
+/// `start` and `end` are byte offsets into the source, while `line` and
+/// `column` are the 1-based position of the first character.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
 }
 
-#[derive(Debug, PartialEq)]
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// An empty span immediately following `self`, where `text` is the
+    /// source text that `self` covers.
+    pub fn following(&self, text: &str) -> Span {
+        let (line, column) = text
+            .chars()
+            .fold((self.line, self.column), |(line, column), c| {
+                if c == '\n' {
+                    (line + 1, 1)
+                } else {
+                    (line, column + 1)
+                }
+            });
+        Span {
+            start: self.end,
+            end: self.end,
+            line,
+            column,
+        }
+    }
+
+    /// A span beginning where `self` begins and ending where `other` ends.
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            ..*self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     // TODO: make this a pointer/ref to a symbol table entry.
     pub lexeme: String,
+    pub span: Span,
 }
 
 impl Token {
@@ -53,6 +217,7 @@ impl Token {
         Token {
             kind: TokenKind::Identifier,
             lexeme: s.into(),
+            span: Span::default(),
         }
     }
 }
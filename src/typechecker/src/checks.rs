@@ -0,0 +1,583 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    rc::Rc,
+};
+
+use ast::{Block, CheckDecl, Expr, ExprVisitor, FieldInit, Literal, MatchArm, Pattern, Stmt};
+use token::{Span, Token, TokenKind};
+
+use crate::{
+    check_arg,
+    scope::Binding,
+    types::{FnType, LiteralDisplay, Type},
+    TypeError,
+};
+
+/// A check declaration whose signature has been resolved.
+pub(crate) struct Check {
+    pub(crate) decl: CheckDecl,
+    pub(crate) subject: Type,
+    pub(crate) params: Vec<Type>,
+}
+
+impl Check {
+    /// The type of the check when it's called like a function, with the
+    /// value being checked as its first argument.
+    pub(crate) fn fn_type(&self) -> FnType {
+        let params = std::iter::once(self.subject.clone())
+            .chain(self.params.iter().cloned())
+            .collect();
+        FnType::new(params, Type::Bool)
+    }
+
+    /// The binding through which the check is called.
+    pub(crate) fn binding(&self) -> Binding {
+        Binding {
+            is_check: true,
+            ..Binding::new(Type::Fn(self.fn_type()))
+        }
+    }
+}
+
+pub(crate) type Checks = HashMap<String, Rc<Check>>;
+
+/// The name of the check which a refinement type refers to as `written`.
+/// Checks are declared in snake_case, but may also be referred to in
+/// UpperCamelCase, e.g. `StrictlyBetween` for `strictly_between`.
+pub(crate) fn declared_name(written: &str) -> String {
+    let mut name = String::new();
+    for (i, c) in written.char_indices() {
+        if c.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(c.to_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Checks that the body of `decl` only uses pure & total constructs, so
+/// that running it can't have any effects, & can't fail unless it runs out
+/// of room. `variants` are the names of the variants of the enums in scope.
+pub(crate) fn check_purity(
+    decl: &CheckDecl,
+    checks: &Checks,
+    variants: &HashSet<String>,
+) -> Result<(), TypeError> {
+    let bound = std::iter::once("value".to_string())
+        .chain(decl.params.iter().map(|p| p.name.lexeme.clone()))
+        .collect();
+    Purity {
+        checks,
+        variants,
+        bound,
+    }
+    .block(&decl.body)
+}
+
+/// Walks the body of a check, tracking the names bound within it, which
+/// may shadow other checks.
+struct Purity<'a> {
+    checks: &'a Checks,
+    variants: &'a HashSet<String>,
+    bound: Vec<String>,
+}
+
+impl Purity<'_> {
+    fn block(&mut self, block: &Block) -> Result<(), TypeError> {
+        let outer = self.bound.len();
+        let result = self.block_contents(block);
+        self.bound.truncate(outer);
+        result
+    }
+
+    fn block_contents(&mut self, block: &Block) -> Result<(), TypeError> {
+        for s in &block.stmts {
+            match s {
+                Stmt::Expr { e } => self.expr(e)?,
+                Stmt::Let {
+                    name, initializer, ..
+                } => {
+                    self.expr(initializer)?;
+                    self.bound.push(name.lexeme.clone());
+                }
+                Stmt::Mut { name, .. } => return Err(impure("Mutable bindings", name.span)),
+                Stmt::Fn { decl } => return Err(impure("Declarations", decl.name.span)),
+                Stmt::Record { decl } => return Err(impure("Declarations", decl.name.span)),
+                Stmt::Enum { decl } => return Err(impure("Declarations", decl.name.span)),
+                Stmt::Interface { decl } => return Err(impure("Declarations", decl.name.span)),
+                Stmt::Impl { decl } => return Err(impure("Declarations", decl.type_name.span)),
+                Stmt::Check { decl } => return Err(impure("Declarations", decl.name.span)),
+            }
+        }
+        match &block.tail {
+            Some(e) => self.expr(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `name` refers to a check, rather than a local binding.
+    fn is_check(&self, name: &Token) -> bool {
+        self.checks.contains_key(&name.lexeme) && !self.bound.contains(&name.lexeme)
+    }
+
+    fn expr(&mut self, e: &Expr) -> Result<(), TypeError> {
+        match e {
+            // The evaluator has no function values, so checks can only be
+            // called.
+            Expr::Identifier { name } if self.is_check(name) => Err(TypeError::new(
+                format!(
+                    "The check {} can only be called within a check",
+                    name.lexeme
+                ),
+                name.span,
+            )),
+            Expr::Identifier { .. } | Expr::Literal { .. } => Ok(()),
+            Expr::FunctionApplication { callee, args, .. } => match callee.as_ref() {
+                Expr::Identifier { name } if self.is_check(name) => {
+                    args.iter().try_for_each(|a| self.expr(a))
+                }
+                _ => Err(TypeError::new(
+                    "Only checks can be called from within a check",
+                    callee.span(),
+                )),
+            },
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Binary { left, op, right } => {
+                self.expr(left)?;
+                self.expr(right)?;
+                let divides = matches!(op.kind, TokenKind::Slash | TokenKind::Percent);
+                match check_arg(right) {
+                    Ok(Literal::Int(divisor)) if divisor != 0 => Ok(()),
+                    _ if divides => Err(TypeError::new(
+                        "Only non-zero Int literals can be divided by within a check",
+                        op.span,
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            Expr::Assign { name, .. } => Err(impure("Assignments", name.span)),
+            Expr::Block { block } => self.block(block),
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition)?;
+                self.block(then_branch)?;
+                match else_branch {
+                    Some(e) => self.expr(e),
+                    None => Ok(()),
+                }
+            }
+            Expr::While { keyword, .. } => Err(impure("`while` loops", keyword.span)),
+            Expr::RecordLiteral { span, .. } => Err(impure("Record literals", *span)),
+            Expr::FieldAccess { object, .. } => self.expr(object),
+            Expr::MethodCall { method, .. } => Err(impure("Method calls", method.span)),
+            Expr::Tuple { elements, .. } => elements.iter().try_for_each(|e| self.expr(e)),
+            Expr::Match {
+                scrutinee,
+                arms,
+                span,
+            } => {
+                if !arms.iter().any(|arm| self.is_catch_all(&arm.pattern)) {
+                    return Err(TypeError::new(
+                        "Matches within a check need an arm which matches any value",
+                        *span,
+                    ));
+                }
+                self.expr(scrutinee)?;
+                for arm in arms {
+                    let outer = self.bound.len();
+                    bind(&arm.pattern, &mut self.bound);
+                    let result = self.expr(&arm.body);
+                    self.bound.truncate(outer);
+                    result?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Purity<'_> {
+    /// Whether `pattern` matches any value it's given. A bare name is only
+    /// known to do so if it doesn't name a variant.
+    fn is_catch_all(&self, pattern: &Pattern) -> bool {
+        match pattern {
+            Pattern::Wildcard { .. } => true,
+            Pattern::Identifier { name } => !self.variants.contains(&name.lexeme),
+            Pattern::Tuple { elements, .. } => elements.iter().all(|p| self.is_catch_all(p)),
+            Pattern::Or { alternatives } => alternatives.iter().any(|p| self.is_catch_all(p)),
+            Pattern::Variant { .. } | Pattern::Literal { .. } => false,
+        }
+    }
+}
+
+/// Adds the names which `pattern` may bind to `bound`.
+fn bind(pattern: &Pattern, bound: &mut Vec<String>) {
+    match pattern {
+        Pattern::Identifier { name } => bound.push(name.lexeme.clone()),
+        Pattern::Variant {
+            fields: Some(fields),
+            ..
+        } => fields.iter().for_each(|p| bind(p, bound)),
+        Pattern::Tuple { elements, .. } => elements.iter().for_each(|p| bind(p, bound)),
+        Pattern::Or { alternatives } => alternatives.iter().for_each(|p| bind(p, bound)),
+        Pattern::Variant { fields: None, .. }
+        | Pattern::Wildcard { .. }
+        | Pattern::Literal { .. } => {}
+    }
+}
+
+fn impure(construct: &str, span: Span) -> TypeError {
+    TypeError::new(
+        format!("{} are not allowed within a check", construct),
+        span,
+    )
+}
+
+/// How deeply checks may call each other, the same limit as the interpreter's.
+const MAX_CALL_DEPTH: usize = 200;
+
+/// Runs `check` on `value`, returning whether the value passes it.
+pub(crate) fn run(
+    check: &Check,
+    value: &Literal,
+    args: &[Literal],
+    checks: &Checks,
+) -> Result<bool, String> {
+    let args = args.iter().map(Value::from).collect();
+    Evaluator {
+        checks,
+        scopes: Vec::new(),
+        call_depth: 0,
+    }
+    .run(check, Value::from(value), args)
+}
+
+/// The values which checks compute with.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    String(String),
+    Int(i64),
+    Bool(bool),
+    Tuple(Vec<Value>),
+    Void,
+}
+
+impl From<&Literal> for Value {
+    fn from(l: &Literal) -> Value {
+        match l {
+            Literal::String(s) => Value::String(s.clone()),
+            Literal::Int(i) => Value::Int(*i),
+            Literal::Bool(b) => Value::Bool(*b),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{}", LiteralDisplay(&Literal::String(s.clone()))),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Tuple(elements) => {
+                write!(f, "(")?;
+                for (i, e) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", e)?;
+                }
+                write!(f, ")")
+            }
+            Value::Void => write!(f, "void"),
+        }
+    }
+}
+
+type EvalResult = Result<Value, String>;
+
+/// Evaluates the bodies of checks which have already been type checked &
+/// found to be pure.
+struct Evaluator<'a> {
+    checks: &'a Checks,
+    scopes: Vec<HashMap<String, Value>>,
+    call_depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn run(&mut self, check: &Check, value: Value, args: Vec<Value>) -> Result<bool, String> {
+        let mut scope = HashMap::from([
+            ("value".to_string(), value),
+            ("okay".to_string(), Value::Bool(true)),
+            ("error".to_string(), Value::Bool(false)),
+        ]);
+        for (param, arg) in check.decl.params.iter().zip(args) {
+            scope.insert(param.name.lexeme.clone(), arg);
+        }
+
+        let outer = std::mem::replace(&mut self.scopes, vec![scope]);
+        let result = self.block(&check.decl.body);
+        self.scopes = outer;
+
+        match result? {
+            Value::Bool(passed) => Ok(passed),
+            v => Err(format!(
+                "The check {} gave {}, not a Bool",
+                check.decl.name.lexeme, v
+            )),
+        }
+    }
+
+    fn block(&mut self, block: &Block) -> EvalResult {
+        self.scopes.push(HashMap::new());
+        let result = self.block_contents(block);
+        self.scopes.pop();
+        result
+    }
+
+    fn block_contents(&mut self, block: &Block) -> EvalResult {
+        for s in &block.stmts {
+            match s {
+                Stmt::Expr { e } => {
+                    e.accept(self)?;
+                }
+                Stmt::Let {
+                    name, initializer, ..
+                } => {
+                    let value = initializer.accept(self)?;
+                    self.define(name.lexeme.clone(), value);
+                }
+                _ => return Err(unsupported("Declarations")),
+            }
+        }
+        match &block.tail {
+            Some(e) => e.accept(self),
+            None => Ok(Value::Void),
+        }
+    }
+
+    fn define(&mut self, name: String, value: Value) {
+        self.scopes
+            .last_mut()
+            .expect("Checks are run within a scope")
+            .insert(name, value);
+    }
+}
+
+/// Whether `value` matches `pattern`, adding any names it binds to
+/// `bindings`.
+fn match_pattern(pattern: &Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> bool {
+    match (pattern, value) {
+        (Pattern::Wildcard { .. }, _) => true,
+        (Pattern::Literal { l, .. }, v) => Value::from(l) == *v,
+        (Pattern::Identifier { name }, v) => {
+            bindings.push((name.lexeme.clone(), v.clone()));
+            true
+        }
+        (
+            Pattern::Variant {
+                enum_name: None,
+                name,
+                fields: Some(fields),
+                ..
+            },
+            Value::Int(i),
+        ) if name.lexeme == "succ" => {
+            *i > 0 && match_pattern(&fields[0], &Value::Int(i - 1), bindings)
+        }
+        (Pattern::Tuple { elements, .. }, Value::Tuple(values)) => elements
+            .iter()
+            .zip(values)
+            .all(|(p, v)| match_pattern(p, v, bindings)),
+        (Pattern::Or { alternatives }, v) => alternatives.iter().any(|p| {
+            let mut alternative_bindings = Vec::new();
+            let matched = match_pattern(p, v, &mut alternative_bindings);
+            if matched {
+                bindings.extend(alternative_bindings);
+            }
+            matched
+        }),
+        _ => false,
+    }
+}
+
+impl ExprVisitor<EvalResult> for Evaluator<'_> {
+    fn visit_identifier(&mut self, name: &Token) -> EvalResult {
+        let value = self.scopes.iter().rev().find_map(|s| s.get(&name.lexeme));
+        value
+            .cloned()
+            .ok_or_else(|| format!("Undefined identifier {}", name.lexeme))
+    }
+
+    fn visit_literal(&mut self, l: &Literal) -> EvalResult {
+        Ok(Value::from(l))
+    }
+
+    fn visit_function_application(&mut self, callee: &Expr, args: &[Expr], _: Span) -> EvalResult {
+        let check = match callee {
+            Expr::Identifier { name } => self.checks.get(&name.lexeme).map(Rc::clone),
+            _ => None,
+        };
+        let Some(check) = check else {
+            return Err(unsupported("Calls to anything but checks"));
+        };
+        let mut args = args
+            .iter()
+            .map(|a| a.accept(self))
+            .collect::<Result<Vec<_>, _>>()?;
+        if args.is_empty() {
+            return Err(format!("No value was given to {}", check.decl.name.lexeme));
+        }
+        let value = args.remove(0);
+
+        // Recursive checks terminate, but may still take too many calls to
+        // run on the type checker's stack.
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err("Maximum call depth exceeded".to_string());
+        }
+        self.call_depth += 1;
+        let outer = std::mem::take(&mut self.scopes);
+        let result = self.run(&check, value, args);
+        self.scopes = outer;
+        self.call_depth -= 1;
+        result.map(Value::Bool)
+    }
+
+    fn visit_unary(&mut self, op: &Token, right: &Expr) -> EvalResult {
+        match (&op.kind, right.accept(self)?) {
+            (TokenKind::Minus, Value::Int(i)) => {
+                i.checked_neg().map(Value::Int).ok_or_else(overflow)
+            }
+            (TokenKind::Bang, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (_, v) => Err(format!("`{}` cannot be applied to {}", op.lexeme, v)),
+        }
+    }
+
+    fn visit_binary(&mut self, left: &Expr, op: &Token, right: &Expr) -> EvalResult {
+        use TokenKind::*;
+
+        let l = left.accept(self)?;
+        // `&&` & `||` short-circuit, just as they do at runtime.
+        match (&op.kind, &l) {
+            (And, Value::Bool(false)) | (Or, Value::Bool(true)) => return Ok(l),
+            (And | Or, _) => return right.accept(self),
+            _ => {}
+        }
+
+        let r = right.accept(self)?;
+        let value = match (&op.kind, l, r) {
+            (EqualEqual, l, r) => Value::Bool(l == r),
+            (BangEqual, l, r) => Value::Bool(l != r),
+            (Plus, Value::String(l), Value::String(r)) => Value::String(l + &r),
+            (Less, Value::Int(l), Value::Int(r)) => Value::Bool(l < r),
+            (LessEqual, Value::Int(l), Value::Int(r)) => Value::Bool(l <= r),
+            (Greater, Value::Int(l), Value::Int(r)) => Value::Bool(l > r),
+            (GreaterEqual, Value::Int(l), Value::Int(r)) => Value::Bool(l >= r),
+            (Slash | Percent, Value::Int(_), Value::Int(0)) => {
+                return Err("Division by zero".to_string())
+            }
+            (kind, Value::Int(l), Value::Int(r)) => {
+                let result = match kind {
+                    Plus => l.checked_add(r),
+                    Minus => l.checked_sub(r),
+                    Star => l.checked_mul(r),
+                    Slash => l.checked_div(r),
+                    Percent => l.checked_rem(r),
+                    _ => return Err(format!("`{}` cannot be applied to Ints", op.lexeme)),
+                };
+                Value::Int(result.ok_or_else(overflow)?)
+            }
+            (_, l, r) => {
+                return Err(format!(
+                    "`{}` cannot be applied to {} and {}",
+                    op.lexeme, l, r
+                ))
+            }
+        };
+        Ok(value)
+    }
+
+    fn visit_assign(&mut self, _: &Token, _: &Expr) -> EvalResult {
+        Err(unsupported("Assignments"))
+    }
+
+    fn visit_block(&mut self, block: &Block) -> EvalResult {
+        self.block(block)
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Block,
+        else_branch: Option<&Expr>,
+    ) -> EvalResult {
+        if condition.accept(self)? == Value::Bool(true) {
+            self.block(then_branch)
+        } else {
+            match else_branch {
+                Some(e) => e.accept(self),
+                None => Ok(Value::Void),
+            }
+        }
+    }
+
+    fn visit_while(&mut self, _: &Expr, _: &Block) -> EvalResult {
+        Err(unsupported("`while` loops"))
+    }
+
+    fn visit_record_literal(&mut self, _: &Token, _: &[FieldInit], _: Span) -> EvalResult {
+        Err(unsupported("Record literals"))
+    }
+
+    fn visit_field_access(&mut self, _: &Expr, name: &Token) -> EvalResult {
+        // Only literals are ever checked, so any field access which is run
+        // names an enum variant, & the evaluator has no enum values.
+        Err(format!(
+            "The variant {} cannot be evaluated at compile time",
+            name.lexeme
+        ))
+    }
+
+    fn visit_method_call(&mut self, _: &Expr, _: &Token, _: &[Expr], _: Span) -> EvalResult {
+        Err(unsupported("Method calls"))
+    }
+
+    fn visit_tuple(&mut self, elements: &[Expr]) -> EvalResult {
+        let values = elements
+            .iter()
+            .map(|e| e.accept(self))
+            .collect::<Result<_, _>>()?;
+        Ok(Value::Tuple(values))
+    }
+
+    fn visit_match(&mut self, scrutinee: &Expr, arms: &[MatchArm], _: Span) -> EvalResult {
+        let value = scrutinee.accept(self)?;
+        for arm in arms {
+            let mut bindings = Vec::new();
+            if match_pattern(&arm.pattern, &value, &mut bindings) {
+                self.scopes.push(bindings.into_iter().collect());
+                let result = arm.body.accept(self);
+                self.scopes.pop();
+                return result;
+            }
+        }
+        Err(format!("No match arm matches {}", value))
+    }
+}
+
+/// For constructs which are rejected in checks before they're ever run.
+fn unsupported(construct: &str) -> String {
+    format!("{} cannot be evaluated at compile time", construct)
+}
+
+fn overflow() -> String {
+    "Integer overflow".to_string()
+}
@@ -0,0 +1,1984 @@
+mod checks;
+mod narrowing;
+mod scope;
+mod substitution;
+mod termination;
+mod types;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    rc::Rc,
+};
+
+use ast::{
+    Block, CheckDecl, Expr, ExprVisitor, Field, FieldInit, FnDecl, ImplDecl, Literal, MatchArm,
+    Param, Pattern, Stmt, TypeExpr, UntypedProgram,
+};
+use checks::{Check, Checks};
+use narrowing::{narrowings, Bounds, Narrowing, Operand};
+use scope::{Binding, Fields, Scopes, TypeDef};
+use substitution::Substitution;
+use token::{Span, Token, TokenKind};
+use types::LiteralDisplay;
+pub use types::{FnType, Refinement, Type, TypeVar};
+
+/// Checks that `program` is well typed, reporting every type error found if
+/// it isn't.
+pub fn typecheck(program: UntypedProgram) -> Result<TypedProgram, Vec<TypeError>> {
+    let mut checker = Checker::new();
+    checker.check_stmts(&program.stmts);
+    if !checker.errors.is_empty() {
+        return Err(checker.errors);
+    }
+
+    let globals = checker
+        .scopes
+        .globals()
+        .map(|(name, ty)| (name.clone(), checker.substitution.apply(ty)))
+        .collect();
+    Ok(TypedProgram { program, globals })
+}
+
+/// A program which is known to be well typed.
+#[derive(Debug)]
+pub struct TypedProgram {
+    program: UntypedProgram,
+    globals: HashMap<String, Type>,
+}
+
+impl TypedProgram {
+    /// The type of the top-level binding `name`.
+    pub fn type_of(&self, name: &str) -> Option<&Type> {
+        self.globals.get(name)
+    }
+
+    /// Discards the type information, e.g. so that the program can be run.
+    pub fn into_untyped(self) -> UntypedProgram {
+        self.program
+    }
+}
+
+/// Describes why a program is not well typed.
+#[derive(Debug, PartialEq)]
+pub struct TypeError {
+    pub message: String,
+    /// The location of the syntax which could not be typed.
+    pub span: Span,
+    /// The location of the syntax which the type expected at `span` was
+    /// inferred from, if there is one.
+    pub origin: Option<Span>,
+}
+
+impl TypeError {
+    fn new<S: Into<String>>(message: S, span: Span) -> TypeError {
+        TypeError {
+            message: message.into(),
+            span,
+            origin: None,
+        }
+    }
+
+    fn with_origin(self, origin: Option<Span>) -> TypeError {
+        TypeError { origin, ..self }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)?;
+        if let Some(origin) = self.origin {
+            write!(f, "\n{}: note: the expected type comes from here", origin)?;
+        }
+        Ok(())
+    }
+}
+
+type TypeResult = Result<Type, TypeError>;
+
+struct Checker {
+    scopes: Scopes,
+    /// The methods declared in `impl` blocks, by type name & then by name.
+    methods: HashMap<String, HashMap<String, FnType>>,
+    /// A `(type, interface)` pair for each `impl Type: Interface`.
+    conformances: HashSet<(String, String)>,
+    /// The declared checks, by name.
+    checks: Checks,
+    /// Checks whose bodies have errors, which are never run.
+    invalid_checks: HashSet<String>,
+    substitution: Substitution,
+    /// The type which the `if`, `match` or block about to be checked must
+    /// have, & where that type was declared. See `check_value`.
+    expected: Option<(Type, Option<Span>)>,
+    errors: Vec<TypeError>,
+}
+
+impl Checker {
+    fn new() -> Checker {
+        let mut substitution = Substitution::default();
+        let printable = substitution.fresh();
+        let mut print_ln = Binding::new(Type::Fn(FnType::new(vec![printable], Type::Void)));
+        print_ln.quantified = vec![0];
+
+        let mut scopes = Scopes::new();
+        scopes.define("print_ln", print_ln);
+
+        Checker {
+            scopes,
+            methods: HashMap::new(),
+            conformances: HashSet::new(),
+            checks: HashMap::new(),
+            invalid_checks: HashSet::new(),
+            substitution,
+            expected: None,
+            errors: Vec::new(),
+        }
+    }
+
+    fn report<T>(&mut self, result: Result<T, TypeError>) -> Option<T> {
+        result.map_err(|e| self.errors.push(e)).ok()
+    }
+
+    /// Checks a sequence of statements which share a scope, carrying on
+    /// after any errors so that they can all be reported.
+    fn check_stmts(&mut self, stmts: &[Stmt]) {
+        self.declare_items(stmts);
+        for s in stmts {
+            let result = self.check_stmt(s);
+            self.report(result);
+        }
+    }
+
+    /// Types, functions & methods are visible throughout the scope they're
+    /// declared in, so they're all declared before any statement is checked.
+    fn declare_items(&mut self, stmts: &[Stmt]) {
+        // Types may refer to each other, so all of them are named before any
+        // of their definitions are resolved.
+        for s in stmts {
+            let (name, placeholder) = match s {
+                Stmt::Record { decl } => (&decl.name, TypeDef::Record { fields: vec![] }),
+                Stmt::Enum { decl } => (&decl.name, TypeDef::Enum { variants: vec![] }),
+                Stmt::Interface { decl } => (&decl.name, TypeDef::Interface { methods: vec![] }),
+                _ => continue,
+            };
+            self.scopes.define_type(&name.lexeme, placeholder);
+        }
+
+        // Checks are declared next, as refinement types in any definition
+        // may refer to them.
+        let mut declared_checks = Vec::new();
+        for s in stmts {
+            if let Stmt::Check { decl } = s {
+                let result = self.declare_check(decl);
+                if self.report(result).is_some() {
+                    declared_checks.push(decl);
+                }
+            }
+        }
+
+        for s in stmts {
+            let result = match s {
+                Stmt::Record { decl } => self.fields(&decl.fields).map(|fields| {
+                    self.scopes
+                        .define_type(&decl.name.lexeme, TypeDef::Record { fields })
+                }),
+                Stmt::Enum { decl } => decl
+                    .variants
+                    .iter()
+                    .map(|v| Ok((v.name.lexeme.clone(), self.fields(&v.fields)?)))
+                    .collect::<Result<_, _>>()
+                    .map(|variants| {
+                        self.scopes
+                            .define_type(&decl.name.lexeme, TypeDef::Enum { variants })
+                    }),
+                Stmt::Interface { decl } => decl
+                    .methods
+                    .iter()
+                    .map(|m| {
+                        Ok((
+                            m.name.lexeme.clone(),
+                            self.fn_type(&m.params, &m.return_type)?,
+                        ))
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(|methods| {
+                        self.scopes
+                            .define_type(&decl.name.lexeme, TypeDef::Interface { methods })
+                    }),
+                _ => Ok(()),
+            };
+            self.report(result);
+        }
+
+        for s in stmts {
+            let result = match s {
+                Stmt::Fn { decl } => self.fn_type(&decl.params, &decl.return_type).map(|t| {
+                    let binding = Binding {
+                        param_origins: decl.params.iter().map(|p| p.ty.span()).collect(),
+                        ..Binding::new(Type::Fn(t))
+                    };
+                    self.scopes.define(&decl.name.lexeme, binding)
+                }),
+                Stmt::Impl { decl } => self.declare_methods(decl),
+                _ => Ok(()),
+            };
+            self.report(result);
+        }
+
+        // Any later statement may run a check, so their bodies are checked
+        // up front.
+        for decl in declared_checks {
+            let errors = self.errors.len();
+            let result = self.check_check(decl);
+            if result.is_err() || self.errors.len() > errors {
+                self.invalid_checks.insert(decl.name.lexeme.clone());
+            }
+            self.report(result);
+        }
+        // Running a check which calls an invalid one would run that too.
+        loop {
+            let calls_invalid: Vec<String> = self
+                .checks
+                .iter()
+                .filter(|(name, _)| !self.invalid_checks.contains(*name))
+                .filter(|(_, check)| {
+                    termination::callees(check, &self.checks)
+                        .iter()
+                        .any(|callee| self.invalid_checks.contains(callee))
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            if calls_invalid.is_empty() {
+                break;
+            }
+            self.invalid_checks.extend(calls_invalid);
+        }
+    }
+
+    /// Checks can be run anywhere in the program, so they're only declared
+    /// at the top level, where every name refers to the same check.
+    fn declare_check(&mut self, decl: &CheckDecl) -> Result<(), TypeError> {
+        if !self.scopes.is_global() {
+            return Err(TypeError::new(
+                "Checks can only be declared at the top level",
+                decl.name.span,
+            ));
+        }
+        if self.checks.contains_key(&decl.name.lexeme) {
+            return Err(TypeError::new(
+                format!("The check {} is already declared", decl.name.lexeme),
+                decl.name.span,
+            ));
+        }
+        let subject = self.resolve(&decl.subject)?;
+        let params = decl
+            .params
+            .iter()
+            .map(|p| self.resolve(&p.ty))
+            .collect::<Result<_, _>>()?;
+        let check = Check {
+            decl: decl.clone(),
+            subject,
+            params,
+        };
+        // Checks can also be called like functions, e.g. as the condition of
+        // an `if`.
+        self.scopes.define(&decl.name.lexeme, check.binding());
+        self.checks.insert(decl.name.lexeme.clone(), Rc::new(check));
+        Ok(())
+    }
+
+    fn declare_methods(&mut self, decl: &ImplDecl) -> Result<(), TypeError> {
+        self.impl_target(&decl.type_name)?;
+        let type_name = &decl.type_name.lexeme;
+
+        let mut signatures = Vec::new();
+        for method in &decl.methods {
+            let signature = self.fn_type(&method.params, &method.return_type)?;
+            signatures.push((method, signature));
+        }
+
+        if let Some(interface) = &decl.interface {
+            let required = match self.scopes.get_type(&interface.lexeme) {
+                Some(TypeDef::Interface { methods }) => methods,
+                _ => {
+                    return Err(TypeError::new(
+                        format!("Unknown interface {}", interface.lexeme),
+                        interface.span,
+                    ))
+                }
+            };
+            check_conformance(decl, &signatures, required)?;
+            self.conformances
+                .insert((type_name.clone(), interface.lexeme.clone()));
+        }
+
+        let methods = self.methods.entry(type_name.clone()).or_default();
+        for (method, signature) in signatures {
+            methods.insert(method.name.lexeme.clone(), signature);
+        }
+        Ok(())
+    }
+
+    /// The type which an `impl` block adds methods to.
+    fn impl_target(&self, type_name: &Token) -> TypeResult {
+        match type_name.lexeme.as_str() {
+            "String" => Ok(Type::String),
+            "Int" => Ok(Type::Int),
+            "Bool" => Ok(Type::Bool),
+            n => match self.scopes.get_type(n) {
+                Some(TypeDef::Record { .. } | TypeDef::Enum { .. }) => Ok(Type::Named(n.into())),
+                _ => Err(TypeError::new(
+                    format!("Cannot implement methods for unknown type {}", n),
+                    type_name.span,
+                )),
+            },
+        }
+    }
+
+    fn check_stmt(&mut self, s: &Stmt) -> Result<(), TypeError> {
+        match s {
+            Stmt::Expr { e } => {
+                e.accept(self)?;
+            }
+            Stmt::Let {
+                name,
+                ty,
+                initializer,
+            } => self.check_binding(name, ty, initializer, false)?,
+            Stmt::Mut {
+                name,
+                ty,
+                initializer,
+            } => self.check_binding(name, ty, initializer, true)?,
+            Stmt::Fn { decl } => {
+                // Any problem with the signature was reported when the
+                // function was declared.
+                if let Ok(signature) = self.fn_type(&decl.params, &decl.return_type) {
+                    self.check_fn_body(decl, None, &signature)?;
+                }
+            }
+            Stmt::Impl { decl } => {
+                let Ok(self_type) = self.impl_target(&decl.type_name) else {
+                    return Ok(());
+                };
+                for method in &decl.methods {
+                    if let Ok(signature) = self.fn_type(&method.params, &method.return_type) {
+                        let result = self.check_fn_body(method, Some(&self_type), &signature);
+                        self.report(result);
+                    }
+                }
+            }
+            // Checks were checked along with the other declarations.
+            Stmt::Record { .. }
+            | Stmt::Enum { .. }
+            | Stmt::Interface { .. }
+            | Stmt::Check { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Checks that the body of a check is pure, always terminates & decides
+    /// whether `value` passes it. Only the check's own parameters, & other
+    /// checks, are visible within it.
+    fn check_check(&mut self, decl: &CheckDecl) -> Result<(), TypeError> {
+        // Any problem with the signature was reported when the check was
+        // declared.
+        let Some(check) = self.checks.get(&decl.name.lexeme).cloned() else {
+            return Ok(());
+        };
+        let variants = self.scopes.variant_names();
+        checks::check_purity(decl, &self.checks, &variants)?;
+        termination::check_termination(&decl.name.lexeme, &self.checks)?;
+
+        self.scopes.push_isolated();
+        for (name, other) in &self.checks {
+            self.scopes.define(name, other.binding());
+        }
+        self.scopes
+            .define("value", Binding::new(check.subject.clone()));
+        self.scopes.define("okay", Binding::new(Type::Bool));
+        self.scopes.define("error", Binding::new(Type::Bool));
+        for (param, ty) in decl.params.iter().zip(&check.params) {
+            self.scopes
+                .define(&param.name.lexeme, Binding::new(ty.clone()));
+        }
+        let result = self.block_contents(&decl.body);
+        self.scopes.pop();
+
+        let body = result?;
+        self.expect_assignable(&body, &Type::Bool, value_span(&decl.body))
+    }
+
+    fn check_binding(
+        &mut self,
+        name: &Token,
+        ty: &Option<TypeExpr>,
+        initializer: &Expr,
+        mutable: bool,
+    ) -> Result<(), TypeError> {
+        let annotated = ty.as_ref().map(|t| self.resolve(t)).transpose()?;
+        let origin = ty.as_ref().map_or(initializer.span(), |t| t.span());
+        let result = match &annotated {
+            Some(t) => self
+                .check_value(initializer, t, Some(origin))
+                .map(|_| t.clone()),
+            None => initializer.accept(self),
+        };
+
+        // Binding an annotated name even when its initializer is ill typed
+        // avoids reporting every later use of it as an error too.
+        let is_annotated = annotated.is_some();
+        let (ty, result) = match (result, annotated) {
+            (Ok(t), _) => (t, Ok(())),
+            (Err(e), Some(t)) => (t, Err(e)),
+            (Err(e), None) => return Err(e),
+        };
+        // Mutable bindings aren't generalised, as each assignment must give
+        // them a value of the same type. For the same reason, an unannotated
+        // mutable binding doesn't keep the refinements of its initial value.
+        let (ty, quantified) = if !mutable {
+            let quantified = self.generalisable_vars(&ty);
+            (ty, quantified)
+        } else if is_annotated {
+            (ty, Vec::new())
+        } else {
+            (self.unrefined(&ty), Vec::new())
+        };
+        let binding = Binding {
+            ty,
+            quantified,
+            mutable,
+            origin: Some(origin),
+            param_origins: Vec::new(),
+            is_check: false,
+            bounds: Bounds::default(),
+        };
+        self.scopes.define(&name.lexeme, binding);
+        result
+    }
+
+    fn check_fn_body(
+        &mut self,
+        decl: &FnDecl,
+        self_type: Option<&Type>,
+        signature: &FnType,
+    ) -> Result<(), TypeError> {
+        self.scopes.push();
+        if let Some(t) = self_type {
+            self.scopes.define("self", Binding::new(t.clone()));
+        }
+        for (param, ty) in decl.params.iter().zip(&signature.params) {
+            let binding = Binding {
+                origin: Some(param.ty.span()),
+                ..Binding::new(ty.clone())
+            };
+            self.scopes.define(&param.name.lexeme, binding);
+        }
+        let origin = decl.return_type.as_ref().map(|t| t.span());
+        let result = self.block_value(&decl.body, &signature.ret, origin);
+        self.scopes.pop();
+        result.map(|_| ())
+    }
+
+    /// The type variables in `t` which no other binding refers to, & so
+    /// which may be instantiated differently each time `t`'s binding is
+    /// used.
+    fn generalisable_vars(&self, t: &Type) -> Vec<TypeVar> {
+        let mut vars = Vec::new();
+        self.substitution.apply(t).vars(&mut vars);
+
+        let mut in_scope = Vec::new();
+        for b in self.scopes.bindings() {
+            let mut binding_vars = Vec::new();
+            self.substitution.apply(&b.ty).vars(&mut binding_vars);
+            in_scope.extend(
+                binding_vars
+                    .into_iter()
+                    .filter(|v| !b.quantified.contains(v)),
+            );
+        }
+        vars.retain(|v| !in_scope.contains(v));
+        vars
+    }
+
+    /// The type of `block`'s value, checking it in the current scope.
+    fn block_contents(&mut self, block: &Block) -> TypeResult {
+        self.check_stmts(&block.stmts);
+        match &block.tail {
+            Some(e) => e.accept(self),
+            None => Ok(Type::Void),
+        }
+    }
+
+    /// Like `block_contents`, but also checks that the block's value is of
+    /// type `expected`, which was declared at `origin`.
+    fn block_value(&mut self, block: &Block, expected: &Type, origin: Option<Span>) -> TypeResult {
+        self.check_stmts(&block.stmts);
+        match &block.tail {
+            Some(e) => self.check_value(e, expected, origin),
+            None => self
+                .expect_assignable(&Type::Void, expected, block.span)
+                .map(|_| Type::Void)
+                .map_err(|e| e.with_origin(origin)),
+        }
+    }
+
+    fn resolve(&self, ty: &TypeExpr) -> TypeResult {
+        match ty {
+            TypeExpr::Named { name } => match name.lexeme.as_str() {
+                "String" => Ok(Type::String),
+                "Int" => Ok(Type::Int),
+                "Bool" => Ok(Type::Bool),
+                n if self.scopes.get_type(n).is_some() => Ok(Type::Named(n.into())),
+                n => Err(TypeError::new(format!("Unknown type {}", n), name.span)),
+            },
+            TypeExpr::Checking {
+                base,
+                check: name,
+                args,
+                span,
+            } => {
+                let base = self.resolve(base)?;
+                let values: Vec<Literal> = args.iter().map(check_arg).collect::<Result<_, _>>()?;
+                let Some(check) = self.checks.get(&checks::declared_name(&name.lexeme)) else {
+                    return Err(TypeError::new(
+                        format!("Unknown check {}", name.lexeme),
+                        name.span,
+                    ));
+                };
+                if *base.unrefined() != check.subject {
+                    return Err(TypeError::new(
+                        format!(
+                            "{} checks values of type {}, not {}",
+                            name.lexeme, check.subject, base
+                        ),
+                        name.span,
+                    ));
+                }
+                if values.len() != check.params.len() {
+                    return Err(TypeError::new(
+                        format!(
+                            "{} expects {} argument(s) but was given {}",
+                            name.lexeme,
+                            check.params.len(),
+                            values.len()
+                        ),
+                        *span,
+                    ));
+                }
+                for ((arg, l), param) in args.iter().zip(&values).zip(&check.params) {
+                    let t = literal_type(l);
+                    if t != *param.unrefined() {
+                        return Err(self.mismatch(param, &t, arg.span()));
+                    }
+                }
+                Ok(Type::Checked(Refinement {
+                    base: Box::new(base),
+                    check: name.lexeme.clone(),
+                    args: values,
+                }))
+            }
+        }
+    }
+
+    fn fields(&self, fields: &[Field]) -> Result<Fields, TypeError> {
+        fields
+            .iter()
+            .map(|f| Ok((f.name.lexeme.clone(), self.resolve(&f.ty)?)))
+            .collect()
+    }
+
+    fn fn_type(
+        &self,
+        params: &[Param],
+        return_type: &Option<TypeExpr>,
+    ) -> Result<FnType, TypeError> {
+        let params = params
+            .iter()
+            .map(|p| self.resolve(&p.ty))
+            .collect::<Result<_, _>>()?;
+        let ret = match return_type {
+            Some(t) => self.resolve(t)?,
+            None => Type::Void,
+        };
+        Ok(FnType::new(params, ret))
+    }
+
+    fn is_assignable(&mut self, from: &Type, to: &Type) -> bool {
+        let conforms = match (
+            self.substitution.apply(from).impl_name(),
+            self.substitution.apply(to),
+        ) {
+            (Some(t), Type::Named(interface)) => {
+                self.conformances.contains(&(t.to_string(), interface))
+            }
+            _ => false,
+        };
+        if conforms || self.substitution.unify(from, to) {
+            return true;
+        }
+        // A refined value can be used wherever a value of the type it
+        // refines is expected, but not the other way around.
+        match self.substitution.apply(from) {
+            Type::Checked(r) => self.is_assignable(&r.base, to),
+            _ => false,
+        }
+    }
+
+    /// `t` with its solved type variables & any refinements removed.
+    fn unrefined(&self, t: &Type) -> Type {
+        self.substitution.apply(t).unrefined().clone()
+    }
+
+    fn expect_assignable(
+        &mut self,
+        found: &Type,
+        expected: &Type,
+        span: Span,
+    ) -> Result<(), TypeError> {
+        if self.is_assignable(found, expected) {
+            Ok(())
+        } else {
+            Err(self.mismatch(expected, found, span))
+        }
+    }
+
+    /// Checks that `e` is a value of type `expected`, which was declared at
+    /// `origin`, returning the type it was found to have.
+    ///
+    /// A refined type is checked separately against each branch of an `if`
+    /// or `match`, & against the tail of a block, so that the bindings which
+    /// are narrowed or declared within each one can be given that type.
+    fn check_value(&mut self, e: &Expr, expected: &Type, origin: Option<Span>) -> TypeResult {
+        let expected_type = self.substitution.apply(expected);
+        let has_branches = matches!(e, Expr::If { .. } | Expr::Match { .. } | Expr::Block { .. });
+        if has_branches && matches!(expected_type, Type::Checked(_)) {
+            self.expected = Some((expected_type, origin));
+        }
+        let found = e.accept(self)?;
+        self.expect_value(e, &found, expected)
+            .map_err(|err| err.with_origin(origin))?;
+        Ok(found)
+    }
+
+    /// Like `expect_assignable`, but a literal `e` can also be given a
+    /// refined type if it passes the refinement's check.
+    ///
+    /// A binding whose value is known to pass the checks can be given a
+    /// refined type too, e.g. within `if x < 5 { .. }`.
+    fn expect_value(&mut self, e: &Expr, found: &Type, expected: &Type) -> Result<(), TypeError> {
+        let expected_type = self.substitution.apply(expected);
+        if matches!(expected_type, Type::Checked(_)) && !self.is_assignable(found, expected) {
+            if let Ok(l) = check_arg(e) {
+                return self.satisfies(&l, &expected_type, e.span());
+            }
+            if let Expr::Identifier { name } = e {
+                let bounds = self.scopes.get(&name.lexeme).map(|b| b.bounds.clone());
+                if self.implied(&bounds.unwrap_or_default(), found, &expected_type) {
+                    return Ok(());
+                }
+            }
+        }
+        self.expect_assignable(found, expected, e.span())
+    }
+
+    /// Whether a value of type `found`, which is within `bounds`, must be a
+    /// value of type `t`. This is only known for checks which just compare
+    /// `value` with Ints.
+    fn implied(&mut self, bounds: &Bounds, found: &Type, t: &Type) -> bool {
+        if self.is_assignable(found, t) {
+            return true;
+        }
+        let Type::Checked(r) = t else {
+            return false;
+        };
+        if !self.implied(bounds, found, &r.base) {
+            return false;
+        }
+        let name = checks::declared_name(&r.check);
+        if self.invalid_checks.contains(&name) {
+            return false;
+        }
+        // Refinements are otherwise compared by name, even for checks which
+        // always pass.
+        let Some(summary) = narrowing::summary(&self.checks[&name]).filter(|s| !s.is_empty())
+        else {
+            return false;
+        };
+        summary.iter().all(|(op, operand)| {
+            let c = match operand {
+                Operand::Literal(i) => *i,
+                Operand::Param(i) => match r.args[*i] {
+                    Literal::Int(i) => i,
+                    _ => return false,
+                },
+            };
+            bounds.implies(op, c)
+        })
+    }
+
+    /// Runs `f` in a new scope, in which the bindings which `narrowings` are
+    /// about have narrower types.
+    fn narrowed<T>(&mut self, narrowings: Vec<Narrowing>, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push();
+        for narrowing in narrowings {
+            let name = match &narrowing {
+                Narrowing::Comparison { name, .. } | Narrowing::Check { name, .. } => name,
+            };
+            // A mutable binding could be assigned a different value before
+            // it's used.
+            let Some(mut binding) = self.scopes.get(name).filter(|b| !b.mutable).cloned() else {
+                continue;
+            };
+            match &narrowing {
+                Narrowing::Comparison { op, value, .. } => {
+                    if self.unrefined(&binding.ty) != Type::Int {
+                        continue;
+                    }
+                    binding.bounds.add(op, *value);
+                }
+                Narrowing::Check { check, args, .. } => {
+                    // The call may be to a function which shadows the check.
+                    let is_check = self.scopes.get(&check.lexeme).is_some_and(|b| b.is_check);
+                    if !is_check || self.invalid_checks.contains(&check.lexeme) {
+                        continue;
+                    }
+                    binding.ty = Type::Checked(Refinement {
+                        base: Box::new(binding.ty),
+                        check: check.lexeme.clone(),
+                        args: args.clone(),
+                    });
+                }
+            }
+            self.scopes.define(name, binding);
+        }
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    /// Checks that the literal `l` is a value of type `t`, running any checks
+    /// which refine it.
+    fn satisfies(&mut self, l: &Literal, t: &Type, span: Span) -> Result<(), TypeError> {
+        let Type::Checked(r) = t else {
+            return self.expect_assignable(&literal_type(l), t, span);
+        };
+        self.satisfies(l, &r.base, span)?;
+        let name = checks::declared_name(&r.check);
+        // Problems with a check have already been reported where it's
+        // declared.
+        if self.invalid_checks.contains(&name) {
+            return Ok(());
+        }
+        let check = &self.checks[&name];
+        match checks::run(check, l, &r.args, &self.checks) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(TypeError::new(
+                format!("{} fails the check {}", LiteralDisplay(l), r),
+                span,
+            )),
+            Err(message) => Err(TypeError::new(
+                format!(
+                    "Running the check {} on {} failed: {}",
+                    r,
+                    LiteralDisplay(l),
+                    message
+                ),
+                span,
+            )),
+        }
+    }
+
+    fn mismatch(&self, expected: &Type, found: &Type, span: Span) -> TypeError {
+        TypeError::new(
+            format!(
+                "Mismatched types: expected {}, found {}",
+                self.substitution.apply(expected),
+                self.substitution.apply(found)
+            ),
+            span,
+        )
+    }
+
+    /// Constrains the types of two pieces of syntax which must agree, e.g.
+    /// the branches of an `if`, reporting both of their locations if they
+    /// can't. Differing refinements of the same type agree on that type.
+    fn expect_same(
+        &mut self,
+        what: &str,
+        first: (&Type, Span),
+        second: (&Type, Span),
+    ) -> TypeResult {
+        if self.substitution.unify(first.0, second.0) {
+            return Ok(first.0.clone());
+        }
+        let (a, b) = (self.unrefined(first.0), self.unrefined(second.0));
+        if self.substitution.unify(&a, &b) {
+            return Ok(a);
+        }
+        let message = format!(
+            "The {} have different types: {} and {}",
+            what,
+            self.substitution.apply(first.0),
+            self.substitution.apply(second.0)
+        );
+        Err(TypeError::new(message, second.1).with_origin(Some(first.1)))
+    }
+
+    /// Whether values of type `t` can be compared with `==`.
+    fn is_comparable(&self, t: &Type) -> bool {
+        match t {
+            Type::Fn(_) => false,
+            Type::Tuple(elements) => elements.iter().all(|e| self.is_comparable(e)),
+            Type::Named(n) => !matches!(self.scopes.get_type(n), Some(TypeDef::Interface { .. })),
+            _ => true,
+        }
+    }
+
+    fn condition(&mut self, e: &Expr) -> Result<(), TypeError> {
+        let t = e.accept(self)?;
+        let t = self.unrefined(&t);
+        if self.substitution.unify(&t, &Type::Bool) {
+            Ok(())
+        } else {
+            Err(TypeError::new(
+                format!(
+                    "Condition must be a Bool, found {}",
+                    self.substitution.apply(&t)
+                ),
+                e.span(),
+            ))
+        }
+    }
+
+    /// Checks a call to a value of type `callee`, whose parameters' types
+    /// were declared at `param_origins`, if that's known.
+    fn call(
+        &mut self,
+        name: &str,
+        callee: Type,
+        callee_span: Span,
+        args: &[Expr],
+        span: Span,
+        param_origins: &[Span],
+    ) -> TypeResult {
+        if let Type::Var(_) = self.substitution.apply(&callee) {
+            let params = args.iter().map(|_| self.substitution.fresh()).collect();
+            let ret = self.substitution.fresh();
+            self.substitution
+                .unify(&callee, &Type::Fn(FnType::new(params, ret)));
+        }
+        let callee = self.substitution.apply(&callee);
+        let Type::Fn(f) = callee else {
+            return Err(TypeError::new(
+                format!("Cannot call a value of type {}", callee),
+                callee_span,
+            ));
+        };
+        if args.len() != f.params.len() {
+            return Err(TypeError::new(
+                format!(
+                    "{} expects {} argument(s) but was given {}",
+                    name,
+                    f.params.len(),
+                    args.len()
+                ),
+                span,
+            ));
+        }
+        for (i, (arg, param)) in args.iter().zip(&f.params).enumerate() {
+            self.check_value(arg, param, param_origins.get(i).copied())?;
+        }
+        Ok(*f.ret)
+    }
+
+    /// Where the type of `e` was determined, if it's a binding whose type
+    /// came from elsewhere in the program.
+    fn origin(&self, e: &Expr) -> Option<Span> {
+        match e {
+            Expr::Identifier { name } => self.scopes.get(&name.lexeme)?.origin,
+            _ => None,
+        }
+    }
+
+    /// If `e` names an enum type, rather than a value, returns that name.
+    fn enum_named_by(&self, e: &Expr) -> Option<String> {
+        let Expr::Identifier { name } = e else {
+            return None;
+        };
+        if self.scopes.get(&name.lexeme).is_some() {
+            return None;
+        }
+        match self.scopes.get_type(&name.lexeme) {
+            Some(TypeDef::Enum { .. }) => Some(name.lexeme.clone()),
+            _ => None,
+        }
+    }
+
+    fn variants(&self, t: &Type) -> Option<&[(String, Fields)]> {
+        match self.substitution.apply(t) {
+            Type::Named(n) => match self.scopes.get_type(&n) {
+                Some(TypeDef::Enum { variants }) => Some(variants),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The type of `Enum.variant`, which is a function for variants with
+    /// fields.
+    fn variant_type(&self, enum_name: &str, variant: &Token) -> TypeResult {
+        let enum_type = Type::Named(enum_name.into());
+        let fields = self
+            .variants(&enum_type)
+            .and_then(|vs| vs.iter().find(|(name, _)| *name == variant.lexeme))
+            .map(|(_, fields)| fields)
+            .ok_or_else(|| {
+                TypeError::new(
+                    format!("{} has no variant {}", enum_name, variant.lexeme),
+                    variant.span,
+                )
+            })?;
+        if fields.is_empty() {
+            Ok(enum_type)
+        } else {
+            let params = fields.iter().map(|(_, t)| t.clone()).collect();
+            Ok(Type::Fn(FnType::new(params, enum_type)))
+        }
+    }
+
+    fn method_type(&self, receiver: &Type, method: &Token) -> Result<FnType, TypeError> {
+        let receiver = &self.unrefined(receiver);
+        let declared = receiver
+            .impl_name()
+            .and_then(|n| self.methods.get(n))
+            .and_then(|methods| methods.get(&method.lexeme));
+        if let Some(signature) = declared {
+            return Ok(signature.clone());
+        }
+        if let Some(signature) = native_method(receiver, &method.lexeme) {
+            return Ok(signature);
+        }
+
+        let from_type = match receiver {
+            Type::Named(n) => match self.scopes.get_type(n) {
+                Some(TypeDef::Interface { methods }) => methods
+                    .iter()
+                    .find(|(name, _)| *name == method.lexeme)
+                    .map(|(_, signature)| signature.clone()),
+                // Calling a field which holds a function.
+                Some(TypeDef::Record { fields }) => fields.iter().find_map(|(name, t)| match t {
+                    Type::Fn(f) if *name == method.lexeme => Some(f.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            },
+            _ => None,
+        };
+        from_type.ok_or_else(|| {
+            TypeError::new(
+                format!("{} has no method {}", receiver, method.lexeme),
+                method.span,
+            )
+        })
+    }
+
+    /// Checks that `pattern` can match values of type `t`, adding the names
+    /// it binds to `bindings`.
+    fn check_pattern(
+        &mut self,
+        pattern: &Pattern,
+        t: &Type,
+        bindings: &mut Vec<(String, Type)>,
+    ) -> Result<(), TypeError> {
+        let refined = self.substitution.apply(t);
+        let t = &self.unrefined(t);
+        match pattern {
+            Pattern::Wildcard { .. } => Ok(()),
+            Pattern::Literal { l, span } => {
+                let literal_type = literal_type(l);
+                if self.substitution.unify(&literal_type, t) {
+                    Ok(())
+                } else {
+                    Err(pattern_mismatch(&literal_type, t, *span))
+                }
+            }
+            Pattern::Identifier { name } => {
+                let is_variant = self
+                    .variants(t)
+                    .is_some_and(|vs| vs.iter().any(|(v, _)| *v == name.lexeme));
+                if !is_variant {
+                    bindings.push((name.lexeme.clone(), refined));
+                }
+                Ok(())
+            }
+            Pattern::Variant {
+                enum_name,
+                name,
+                fields,
+                span,
+            } => {
+                if let Some(enum_name) = enum_name {
+                    let pattern_type = Type::Named(enum_name.lexeme.clone());
+                    if !self.substitution.unify(&pattern_type, t) {
+                        return Err(pattern_mismatch(&pattern_type, t, *span));
+                    }
+                }
+                let t = &self.substitution.apply(t);
+                if let (None, Some([field])) = (enum_name, fields.as_deref()) {
+                    // `succ(n)` matches any Int greater than zero, binding `n`
+                    // to the Int before it.
+                    if name.lexeme == "succ" && self.variants(t).is_none() {
+                        if !self.substitution.unify(&Type::Int, t) {
+                            return Err(pattern_mismatch(&Type::Int, t, *span));
+                        }
+                        return self.check_pattern(field, &Type::Int, bindings);
+                    }
+                }
+                let Some(variants) = self.variants(t) else {
+                    return Err(TypeError::new(
+                        format!("Variant {} cannot match a value of type {}", name.lexeme, t),
+                        *span,
+                    ));
+                };
+                let Some((_, field_types)) = variants.iter().find(|(v, _)| *v == name.lexeme)
+                else {
+                    return Err(TypeError::new(
+                        format!("{} has no variant {}", t, name.lexeme),
+                        name.span,
+                    ));
+                };
+                let field_types = field_types.clone();
+                let Some(patterns) = fields else {
+                    return Ok(());
+                };
+                if patterns.len() != field_types.len() {
+                    return Err(TypeError::new(
+                        format!(
+                            "{}.{} has {} field(s) but the pattern has {}",
+                            t,
+                            name.lexeme,
+                            field_types.len(),
+                            patterns.len()
+                        ),
+                        *span,
+                    ));
+                }
+                for (p, (_, field_type)) in patterns.iter().zip(&field_types) {
+                    self.check_pattern(p, field_type, bindings)?;
+                }
+                Ok(())
+            }
+            Pattern::Tuple { elements, span } => {
+                let types: Vec<Type> = elements.iter().map(|_| self.substitution.fresh()).collect();
+                if !self.substitution.unify(&Type::Tuple(types.clone()), t) {
+                    return Err(TypeError::new(
+                        format!(
+                            "A tuple pattern of {} elements cannot match a value of type {}",
+                            elements.len(),
+                            t
+                        ),
+                        *span,
+                    ));
+                }
+                for (p, element_type) in elements.iter().zip(&types) {
+                    self.check_pattern(p, element_type, bindings)?;
+                }
+                Ok(())
+            }
+            Pattern::Or { alternatives } => {
+                let mut bound: Option<Vec<(String, Type)>> = None;
+                for alternative in alternatives {
+                    let mut alternative_bindings = Vec::new();
+                    self.check_pattern(alternative, t, &mut alternative_bindings)?;
+                    alternative_bindings.sort_by(|a, b| a.0.cmp(&b.0));
+                    match &bound {
+                        None => bound = Some(alternative_bindings),
+                        Some(b)
+                            if b.len() == alternative_bindings.len()
+                                && b.iter().zip(&alternative_bindings).all(|(x, y)| {
+                                    x.0 == y.0 && self.substitution.unify(&x.1, &y.1)
+                                }) => {}
+                        Some(_) => {
+                            return Err(TypeError::new(
+                                "Alternatives of an or-pattern must bind the same names",
+                                alternative.span(),
+                            ))
+                        }
+                    }
+                }
+                bindings.extend(bound.unwrap_or_default());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Checks that an `impl` provides exactly the methods required by an
+/// interface, with the same parameter & return types.
+fn check_conformance(
+    decl: &ImplDecl,
+    signatures: &[(&FnDecl, FnType)],
+    required: &[(String, FnType)],
+) -> Result<(), TypeError> {
+    let interface_name = &decl
+        .interface
+        .as_ref()
+        .expect("Impl has an interface")
+        .lexeme;
+    for (method, signature) in signatures {
+        let name = &method.name.lexeme;
+        match required.iter().find(|(n, _)| n == name) {
+            None => {
+                return Err(TypeError::new(
+                    format!("{} is not a method of {}", name, interface_name),
+                    method.name.span,
+                ))
+            }
+            Some((_, expected)) if expected != signature => {
+                return Err(TypeError::new(
+                    format!(
+                        "{} does not match its signature in {}: expected {}, found {}",
+                        name, interface_name, expected, signature
+                    ),
+                    method.name.span,
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let missing: Vec<&str> = required
+        .iter()
+        .filter(|(n, _)| !signatures.iter().any(|(m, _)| m.name.lexeme == *n))
+        .map(|(n, _)| n.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(TypeError::new(
+            format!(
+                "{} is missing method(s) {} required by {}",
+                decl.type_name.lexeme,
+                missing.join(", "),
+                interface_name
+            ),
+            decl.type_name.span,
+        ));
+    }
+    Ok(())
+}
+
+/// The signatures of the methods which every value of a built-in type has.
+fn native_method(receiver: &Type, method: &str) -> Option<FnType> {
+    let signature = match (receiver, method) {
+        (Type::String, "appending") => FnType::new(vec![Type::String], Type::String),
+        (Type::String, "is_empty") => FnType::new(vec![], Type::Bool),
+        (Type::String, "length") => FnType::new(vec![], Type::Int),
+        (Type::String | Type::Int | Type::Bool, "to_string") => FnType::new(vec![], Type::String),
+        _ => return None,
+    };
+    Some(signature)
+}
+
+/// What's known about the Int being matched, & any name the whole of it is
+/// bound to, within an arm whose `pattern` is only tried if the Ints in
+/// `matched` weren't.
+fn arm_narrowings(
+    scrutinee: &Expr,
+    shadowed: bool,
+    pattern: &Pattern,
+    matched: &[i64],
+) -> Vec<Narrowing> {
+    let mut comparisons: Vec<(TokenKind, i64)> =
+        matched.iter().map(|i| (TokenKind::BangEqual, *i)).collect();
+    match pattern {
+        Pattern::Literal {
+            l: Literal::Int(i), ..
+        } => comparisons.push((TokenKind::EqualEqual, *i)),
+        Pattern::Variant {
+            enum_name: None,
+            name,
+            fields: Some(_),
+            ..
+        } if name.lexeme == "succ" => comparisons.push((TokenKind::Greater, 0)),
+        _ => {}
+    }
+
+    let mut names = Vec::new();
+    match scrutinee {
+        Expr::Identifier { name } if !shadowed => names.push(&name.lexeme),
+        _ => {}
+    }
+    if let Pattern::Identifier { name } = pattern {
+        names.push(&name.lexeme);
+    }
+    names
+        .into_iter()
+        .flat_map(|name| {
+            comparisons.iter().map(|(op, value)| Narrowing::Comparison {
+                name: name.clone(),
+                op: op.clone(),
+                value: *value,
+            })
+        })
+        .collect()
+}
+
+/// The value of an argument to a check, which must be known without running
+/// the program.
+fn check_arg(e: &Expr) -> Result<Literal, TypeError> {
+    match e {
+        Expr::Literal { l, .. } => Ok(l.clone()),
+        Expr::Unary { op, right } if op.kind == TokenKind::Minus => match right.as_ref() {
+            Expr::Literal {
+                l: Literal::Int(i), ..
+            } => Ok(Literal::Int(-i)),
+            _ => Err(TypeError::new(
+                "The arguments of a check must be literals",
+                e.span(),
+            )),
+        },
+        _ => Err(TypeError::new(
+            "The arguments of a check must be literals",
+            e.span(),
+        )),
+    }
+}
+
+/// The span of the expression which gives `block` its value, or of the whole
+/// block if it doesn't have one.
+fn value_span(block: &Block) -> Span {
+    match &block.tail {
+        Some(tail) => tail.span(),
+        None => block.span,
+    }
+}
+
+fn literal_type(l: &Literal) -> Type {
+    match l {
+        Literal::String(_) => Type::String,
+        Literal::Int(_) => Type::Int,
+        Literal::Bool(_) => Type::Bool,
+    }
+}
+
+fn pattern_mismatch(pattern_type: &Type, t: &Type, span: Span) -> TypeError {
+    TypeError::new(
+        format!(
+            "A pattern of type {} cannot match a value of type {}",
+            pattern_type, t
+        ),
+        span,
+    )
+}
+
+fn undefined(name: &Token) -> TypeError {
+    TypeError::new(format!("Undefined identifier {}", name.lexeme), name.span)
+}
+
+fn no_such_field(t: &Type, field: &Token) -> TypeError {
+    TypeError::new(format!("{} has no field {}", t, field.lexeme), field.span)
+}
+
+impl ExprVisitor<TypeResult> for Checker {
+    fn visit_identifier(&mut self, name: &Token) -> TypeResult {
+        match self.scopes.get(&name.lexeme) {
+            Some(binding) => Ok(self
+                .substitution
+                .instantiate(&binding.ty, &binding.quantified)),
+            None if self.scopes.get_type(&name.lexeme).is_some() => Err(TypeError::new(
+                format!("{} is a type, not a value", name.lexeme),
+                name.span,
+            )),
+            None => Err(undefined(name)),
+        }
+    }
+
+    fn visit_literal(&mut self, l: &Literal) -> TypeResult {
+        Ok(literal_type(l))
+    }
+
+    fn visit_function_application(
+        &mut self,
+        callee: &Expr,
+        args: &[Expr],
+        span: Span,
+    ) -> TypeResult {
+        let callee_type = callee.accept(self)?;
+        let (name, param_origins) = match callee {
+            Expr::Identifier { name } => (
+                name.lexeme.as_str(),
+                self.scopes
+                    .get(&name.lexeme)
+                    .map(|b| b.param_origins.clone())
+                    .unwrap_or_default(),
+            ),
+            _ => ("function", Vec::new()),
+        };
+        self.call(name, callee_type, callee.span(), args, span, &param_origins)
+    }
+
+    fn visit_unary(&mut self, op: &Token, right: &Expr) -> TypeResult {
+        let t = right.accept(self)?;
+        let t = self.unrefined(&t);
+        let operand = match op.kind {
+            TokenKind::Bang => Type::Bool,
+            _ => Type::Int,
+        };
+        if self.substitution.unify(&t, &operand) {
+            Ok(operand)
+        } else {
+            Err(TypeError::new(
+                format!(
+                    "Operator `{}` cannot be applied to {}",
+                    op.lexeme,
+                    self.substitution.apply(&t)
+                ),
+                op.span,
+            ))
+        }
+    }
+
+    fn visit_binary(&mut self, left: &Expr, op: &Token, right: &Expr) -> TypeResult {
+        use TokenKind::*;
+
+        let l = left.accept(self)?;
+        let l = self.unrefined(&l);
+        let r = right.accept(self)?;
+        let r = self.unrefined(&r);
+        let s = &mut self.substitution;
+        let result = match &op.kind {
+            // `+` also concatenates strings, so operands which are otherwise
+            // unconstrained are only assumed to be Ints.
+            Plus if s.unify(&l, &r) => match s.apply(&l) {
+                Type::String => Some(Type::String),
+                t => s.unify(&t, &Type::Int).then_some(Type::Int),
+            },
+            Minus | Star | Slash | Percent => {
+                (s.unify(&l, &Type::Int) && s.unify(&r, &Type::Int)).then_some(Type::Int)
+            }
+            Less | LessEqual | Greater | GreaterEqual => {
+                (s.unify(&l, &Type::Int) && s.unify(&r, &Type::Int)).then_some(Type::Bool)
+            }
+            EqualEqual | BangEqual if s.unify(&l, &r) => {
+                let operand = s.apply(&l);
+                self.is_comparable(&operand).then_some(Type::Bool)
+            }
+            And | Or => {
+                (s.unify(&l, &Type::Bool) && s.unify(&r, &Type::Bool)).then_some(Type::Bool)
+            }
+            _ => None,
+        };
+        result.ok_or_else(|| {
+            TypeError::new(
+                format!(
+                    "Operator `{}` cannot be applied to {} and {}",
+                    op.lexeme,
+                    self.substitution.apply(&l),
+                    self.substitution.apply(&r)
+                ),
+                op.span,
+            )
+            .with_origin(self.origin(left).or_else(|| self.origin(right)))
+        })
+    }
+
+    fn visit_assign(&mut self, name: &Token, value: &Expr) -> TypeResult {
+        let binding = self
+            .scopes
+            .get(&name.lexeme)
+            .ok_or_else(|| undefined(name))?;
+        if !binding.mutable {
+            return Err(TypeError::new(
+                format!("Cannot assign to immutable binding {}", name.lexeme),
+                name.span,
+            ));
+        }
+        let (expected, origin) = (binding.ty.clone(), binding.origin);
+        self.check_value(value, &expected, origin)?;
+        Ok(Type::Void)
+    }
+
+    fn visit_block(&mut self, block: &Block) -> TypeResult {
+        let expected = self.expected.take();
+        self.scopes.push();
+        let result = match &expected {
+            Some((t, origin)) => self.block_value(block, t, *origin).map(|_| t.clone()),
+            None => self.block_contents(block),
+        };
+        self.scopes.pop();
+        result
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Block,
+        else_branch: Option<&Expr>,
+    ) -> TypeResult {
+        let expected = self.expected.take();
+        self.condition(condition)?;
+        let then_type = self.narrowed(narrowings(condition, true), |c| {
+            c.expected.clone_from(&expected);
+            c.visit_block(then_branch)
+        })?;
+        let Some(else_branch) = else_branch else {
+            return Ok(Type::Void);
+        };
+        if let Some((t, origin)) = expected {
+            self.narrowed(narrowings(condition, false), |c| {
+                c.check_value(else_branch, &t, origin)
+            })?;
+            return Ok(t);
+        }
+        let else_type = self.narrowed(narrowings(condition, false), |c| else_branch.accept(c))?;
+        self.expect_same(
+            "branches of an if",
+            (&then_type, value_span(then_branch)),
+            (&else_type, else_branch.span()),
+        )
+    }
+
+    fn visit_while(&mut self, condition: &Expr, body: &Block) -> TypeResult {
+        self.condition(condition)?;
+        self.visit_block(body)?;
+        Ok(Type::Void)
+    }
+
+    fn visit_record_literal(
+        &mut self,
+        name: &Token,
+        fields: &[FieldInit],
+        span: Span,
+    ) -> TypeResult {
+        let declared = match self.scopes.get_type(&name.lexeme) {
+            Some(TypeDef::Record { fields }) => fields.clone(),
+            _ => {
+                return Err(TypeError::new(
+                    format!("{} is not a record type", name.lexeme),
+                    name.span,
+                ))
+            }
+        };
+        let record_type = Type::Named(name.lexeme.clone());
+
+        let mut given = HashSet::new();
+        for init in fields {
+            let Some((_, field_type)) = declared.iter().find(|(n, _)| *n == init.name.lexeme)
+            else {
+                return Err(no_such_field(&record_type, &init.name));
+            };
+            if !given.insert(&init.name.lexeme) {
+                return Err(TypeError::new(
+                    format!("Field {} is given more than once", init.name.lexeme),
+                    init.name.span,
+                ));
+            }
+            self.check_value(&init.value, field_type, None)?;
+        }
+
+        let missing: Vec<&str> = declared
+            .iter()
+            .filter(|(n, _)| !given.contains(n))
+            .map(|(n, _)| n.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(TypeError::new(
+                format!("{} is missing field(s) {}", name.lexeme, missing.join(", ")),
+                span,
+            ));
+        }
+        Ok(record_type)
+    }
+
+    fn visit_field_access(&mut self, object: &Expr, name: &Token) -> TypeResult {
+        if let Some(enum_name) = self.enum_named_by(object) {
+            return self.variant_type(&enum_name, name);
+        }
+        let t = object.accept(self)?;
+        let t = self.unrefined(&t);
+        let fields = match &t {
+            Type::Named(n) => match self.scopes.get_type(n) {
+                Some(TypeDef::Record { fields }) => Some(fields),
+                _ => None,
+            },
+            _ => None,
+        };
+        fields
+            .and_then(|fields| fields.iter().find(|(n, _)| *n == name.lexeme))
+            .map(|(_, field_type)| field_type.clone())
+            .ok_or_else(|| no_such_field(&t, name))
+    }
+
+    fn visit_method_call(
+        &mut self,
+        receiver: &Expr,
+        method: &Token,
+        args: &[Expr],
+        span: Span,
+    ) -> TypeResult {
+        let callee_span = receiver.span().to(method.span);
+        if let Some(enum_name) = self.enum_named_by(receiver) {
+            let callee = self.variant_type(&enum_name, method)?;
+            let name = format!("{}.{}", enum_name, method.lexeme);
+            return self.call(&name, callee, callee_span, args, span, &[]);
+        }
+
+        let t = receiver.accept(self)?;
+        let t = self.substitution.apply(&t);
+        let signature = self.method_type(&t, method)?;
+        let name = format!("{}.{}", t, method.lexeme);
+        self.call(&name, Type::Fn(signature), callee_span, args, span, &[])
+    }
+
+    fn visit_tuple(&mut self, elements: &[Expr]) -> TypeResult {
+        let types = elements
+            .iter()
+            .map(|e| e.accept(self))
+            .collect::<Result<_, _>>()?;
+        Ok(Type::Tuple(types))
+    }
+
+    fn visit_match(&mut self, scrutinee: &Expr, arms: &[MatchArm], _span: Span) -> TypeResult {
+        let expected = self.expected.take();
+        let t = scrutinee.accept(self)?;
+        let mut result: Option<(Type, Span)> = None;
+        // The Ints matched by earlier arms, which later arms can't match.
+        let mut matched = Vec::new();
+        for arm in arms {
+            let mut bindings = Vec::new();
+            self.check_pattern(&arm.pattern, &t, &mut bindings)?;
+
+            // The pattern may shadow the binding being matched.
+            let shadowed = match scrutinee {
+                Expr::Identifier { name } => bindings.iter().any(|(n, _)| *n == name.lexeme),
+                _ => true,
+            };
+            let narrowings = arm_narrowings(scrutinee, shadowed, &arm.pattern, &matched);
+            self.scopes.push();
+            for (name, ty) in bindings {
+                self.scopes.define(name, Binding::new(ty));
+            }
+            let body = self.narrowed(narrowings, |c| match &expected {
+                Some((t, origin)) => c.check_value(&arm.body, t, *origin),
+                None => arm.body.accept(c),
+            });
+            self.scopes.pop();
+            match &arm.pattern {
+                Pattern::Literal {
+                    l: Literal::Int(i), ..
+                } => matched.push(*i),
+                Pattern::Or { alternatives } => {
+                    matched.extend(alternatives.iter().filter_map(|p| match p {
+                        Pattern::Literal {
+                            l: Literal::Int(i), ..
+                        } => Some(*i),
+                        _ => None,
+                    }))
+                }
+                _ => {}
+            }
+
+            let body = (body?, arm.body.span());
+            if let Some((t, _)) = &expected {
+                result = Some((t.clone(), body.1));
+                continue;
+            }
+            result = match result {
+                None => Some(body),
+                Some(first) => {
+                    let joined = self.expect_same(
+                        "arms of a match",
+                        (&first.0, first.1),
+                        (&body.0, body.1),
+                    )?;
+                    Some((joined, first.1))
+                }
+            }
+        }
+        // A match without any arms never produces a value, so its type is
+        // whatever its context needs it to be.
+        Ok(result.map_or_else(|| self.substitution.fresh(), |(t, _)| t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::token_stream;
+    use parser::parse;
+    use test_with_parameters::*;
+
+    #[test_with_parameters(
+        [ src                                                                  , name    , expected                  ]
+        [ "let x = 1;"                                                         , "x"     , Type::Int                 ]
+        [ "let x = \"a\" + \"b\";"                                             , "x"     , Type::String              ]
+        [ "let x = 1 < 2 && true;"                                             , "x"     , Type::Bool                ]
+        [ "let x = print_ln(1);"                                               , "x"     , Type::Void                ]
+        [ "mut x = 1; x = x * 2;"                                              , "x"     , Type::Int                 ]
+        [ "let x = { let y = 1; y == 2 };"                                     , "x"     , Type::Bool                ]
+        [ "let x = if true { 1 } else { 2 };"                                  , "x"     , Type::Int                 ]
+        [ "let x = if true { 1 };"                                             , "x"     , Type::Void                ]
+        [ "fn f(a: Int, b: String) -> Bool { true }"                           , "f"     , Type::Fn(FnType::new(vec![Type::Int, Type::String], Type::Bool)) ]
+        [ "fn f() {}"                                                          , "f"     , Type::Fn(FnType::new(vec![], Type::Void)) ]
+        [ "let x = f(); fn f() -> Int { g() } fn g() -> Int { 1 }"             , "x"     , Type::Int                 ]
+        [ "print_ln(\"started\"); let x = 1; fn get() -> Int { x + 1 } let x = \"s\";
+           let y: Int = get(); print_ln(y + 1);"                               , "y"     , Type::Int                 ]
+        [ "let x = (1, \"a\");"                                                , "x"     , Type::Tuple(vec![Type::Int, Type::String]) ]
+        [ "record P { a: Int, q: Q } record Q { b: Bool } let x = P { q: Q { b: true }, a: 1 }.q.b;", "x", Type::Bool ]
+        [ "enum E { a, b(x: Int) } let x = E.a;"                               , "x"     , Type::Named("E".into())   ]
+        [ "enum E { a, b(x: Int) } let x = E.b;"                               , "x"     , Type::Fn(FnType::new(vec![Type::Int], Type::Named("E".into()))) ]
+        [ "enum E { a, b(x: Int) } let x = match E.b(1) { a => 0, b(n) => n };", "x"     , Type::Int                 ]
+        [ "let x = match (1, true) { (0, b) | (_, b) => b };"                  , "x"     , Type::Bool                ]
+        [ "let x = \"a\".appending(\"b\").length().to_string();"               , "x"     , Type::String              ]
+        [ "impl Int { fn double() -> Int { self * 2 } } let x = 2.double();"   , "x"     , Type::Int                 ]
+        [ "mut x = match 1 { }; x = \"a\";"                        , "x"     , Type::String              ]
+        [ "let p = print_ln; let x = (p(1), p(\"a\"));"             , "x"     , Type::Tuple(vec![Type::Void, Type::Void]) ]
+        [ "let never = match 1 { }; let x = (never + 1, never == \"a\");", "x" , Type::Tuple(vec![Type::Int, Type::Bool]) ]
+        [ "let x = match 1 { } + match 2 { };"                       , "x"     , Type::Int                 ]
+        [ "interface I { fn f() -> Int } impl Int: I { fn f() -> Int { self } }
+           fn g(i: I) -> Int { i.f() } let x = g(1);"                          , "x"     , Type::Int                 ]
+        [ "interface I { fn f() -> Int } record R {} impl R: I { fn f() -> Int { 1 } }
+           let x: I = R {};"                                                   , "x"     , Type::Named("I".into())   ]
+    )]
+    fn well_typed_programs(src: &str, name: &str, expected: Type) {
+        let program = util::check(src).unwrap();
+        assert_eq!(Some(&expected), program.type_of(name))
+    }
+
+    #[test_with_parameters(
+        [ src                                                    , message                                                   , column ]
+        [ "let x: String = 1;"                                   , "Mismatched types: expected String, found Int"            , 17     ]
+        [ "let x: Nope = 1;"                                     , "Unknown type Nope"                                       , 8      ]
+        [ "y;"                                                   , "Undefined identifier y"                                  , 1      ]
+        [ "1 + \"a\";"                                           , "Operator `+` cannot be applied to Int and String"        , 3      ]
+        [ "-true;"                                               , "Operator `-` cannot be applied to Bool"                  , 1      ]
+        [ "1 && true;"                                           , "Operator `&&` cannot be applied to Int and Bool"         , 3      ]
+        [ "fn f() {} f == f;"                                    , "Operator `==` cannot be applied to fn() -> Void and fn() -> Void", 13 ]
+        [ "print_ln(1) + 1;"                                     , "Operator `+` cannot be applied to Void and Int"          , 13     ]
+        [ "let f = print_ln; f(1, 2);"                           , "f expects 1 argument(s) but was given 2"                 , 19     ]
+        [ "let x = 1; x = 2;"                                    , "Cannot assign to immutable binding x"                    , 12     ]
+        [ "mut x = 1; x = \"a\";"                                , "Mismatched types: expected Int, found String"            , 16     ]
+        [ "if 1 { }"                                             , "Condition must be a Bool, found Int"                     , 4      ]
+        [ "while \"a\" { }"                                      , "Condition must be a Bool, found String"                  , 7      ]
+        [ "if true { 1 } else { \"a\" };"                        , "The branches of an if have different types: Int and String", 20   ]
+        [ "fn f(a: Int) -> String { a }"                         , "Mismatched types: expected String, found Int"            , 26     ]
+        [ "fn f() -> Int { }"                                    , "Mismatched types: expected Int, found Void"              , 15     ]
+        [ "fn f() { 1 }"                                         , "Mismatched types: expected Void, found Int"              , 10     ]
+        [ "fn f(a: Int) {} f();"                                 , "f expects 1 argument(s) but was given 0"                 , 17     ]
+        [ "fn f(a: Int) {} f(true);"                             , "Mismatched types: expected Int, found Bool"              , 19     ]
+        [ "1();"                                                 , "Cannot call a value of type Int"                         , 1      ]
+        [ "{ let y = 1; } y;"                                    , "Undefined identifier y"                                  , 16     ]
+        [ "record P { a: Int } P { };"                           , "P is missing field(s) a"                                 , 21     ]
+        [ "record P { a: Int } P { a: 1, b: 2 };"                , "P has no field b"                                        , 31     ]
+        [ "record P { a: Int } P { a: 1, a: 2 };"                , "Field a is given more than once"                         , 31     ]
+        [ "record P { a: Int } P { a: true };"                   , "Mismatched types: expected Int, found Bool"              , 28     ]
+        [ "record P { a: Int } P { a: 1 }.b;"                    , "P has no field b"                                        , 32     ]
+        [ "Q { };"                                               , "Q is not a record type"                                  , 1      ]
+        [ "record P { a: Int } P;"                               , "P is a type, not a value"                                , 21     ]
+        [ "enum E { a } E.b;"                                    , "E has no variant b"                                      , 16     ]
+        [ "enum E { a(x: Int) } E.a(true);"                      , "Mismatched types: expected Int, found Bool"              , 26     ]
+        [ "enum E { a(x: Int) } E.a();"                          , "E.a expects 1 argument(s) but was given 0"               , 22     ]
+        [ "match 1 { \"a\" => 1 };"                              , "A pattern of type String cannot match a value of type Int", 11     ]
+        [ "enum E { a } match 1 { a(x) => 1 };"                  , "Variant a cannot match a value of type Int"              , 24     ]
+        [ "enum E { a } enum F { a } match E.a { F.a => 1 };"    , "A pattern of type F cannot match a value of type E"      , 39     ]
+        [ "enum E { a(x: Int) } match E.a(1) { a(x, y) => 1 };"  , "E.a has 1 field(s) but the pattern has 2"                , 37     ]
+        [ "match (1, 2) { (a, b, c) => 1 };"                     , "A tuple pattern of 3 elements cannot match a value of type (Int, Int)", 16 ]
+        [ "match (1, 2) { (a, _) | (_, b) => 1 };"               , "Alternatives of an or-pattern must bind the same names"  , 25     ]
+        [ "match 1 { 1 => 1, _ => \"a\" };"                      , "The arms of a match have different types: Int and String", 24     ]
+        [ "1.nope();"                                            , "Int has no method nope"                                  , 3      ]
+        [ "\"a\".appending(1);"                                  , "Mismatched types: expected String, found Int"            , 15     ]
+        [ "impl Nope { }"                                        , "Cannot implement methods for unknown type Nope"          , 6      ]
+        [ "interface I { fn f() -> Int } impl Int: I { }"        , "Int is missing method(s) f required by I"                , 36     ]
+        [ "interface I { fn f() -> Int } impl Int: I { fn f() {} }", "f does not match its signature in I: expected fn() -> Int, found fn() -> Void", 48 ]
+        [ "interface I { } impl Int: I { fn f() {} }"            , "f is not a method of I"                                  , 34     ]
+        [ "impl Int: Nope { }"                                   , "Unknown interface Nope"                                  , 11     ]
+        [ "interface I { fn f() -> Int } record R {} let x: I = R {};", "Mismatched types: expected I, found R"              , 54     ]
+        [ "interface I { } impl Int: I {} fn f(a: I) -> Bool { a == a }", "Operator `==` cannot be applied to I and I"       , 55     ]
+        [ "impl Int { fn f() -> Int { self } } self;"            , "Undefined identifier self"                               , 37     ]
+    )]
+    fn ill_typed_programs(src: &str, message: &str, column: usize) {
+        let errs = util::check(src).unwrap_err();
+        assert_eq!(message, errs[0].message);
+        assert_eq!(column, errs[0].span.column);
+    }
+
+    #[test_with_parameters(
+        [ src                                                                  , name    , expected                  ]
+        [ "fn f(a: Int checking Positive) -> Int { a * 2 }"                    , "f"     , Type::Fn(FnType::new(vec![Type::Checked(Refinement { base: Box::new(Type::Int), check: "Positive".into(), args: vec![] })], Type::Int)) ]
+        [ "fn f(a: String checking NonEmpty) -> Int { a.length() } let x = f;" , "x"     , Type::Fn(FnType::new(vec![Type::Checked(Refinement { base: Box::new(Type::String), check: "NonEmpty".into(), args: vec![] })], Type::Int)) ]
+        [ "fn f(a: Int checking P) -> Int checking P { a } let x = f;"         , "x"     , Type::Fn(FnType::new(vec![Type::Checked(Refinement { base: Box::new(Type::Int), check: "P".into(), args: vec![] })], Type::Checked(Refinement { base: Box::new(Type::Int), check: "P".into(), args: vec![] }))) ]
+        [ "fn f(a: Int checking P, b: Int checking Q) -> Int { if a > b { a } else { b } } let x = f;", "x", Type::Fn(FnType::new(vec![Type::Checked(Refinement { base: Box::new(Type::Int), check: "P".into(), args: vec![] }), Type::Checked(Refinement { base: Box::new(Type::Int), check: "Q".into(), args: vec![] })], Type::Int)) ]
+        [ "fn f(a: Int checking P) -> Int { mut x = a; x = 1; x } let x = f(match 1 { });", "x", Type::Int ]
+        [ "record P { age: Int checking Between(0, 130) } fn f(p: P) -> Bool { match p.age { 0 => false, age => age == 1 } } let x = f;", "x", Type::Fn(FnType::new(vec![Type::Named("P".into())], Type::Bool)) ]
+        [ "record Person { name: String checking NonEmpty, age: Int checking StrictlyBetween(0, 130) }
+           let me = Person { name: \"Matthew\", age: 29 };"                    , "me"    , Type::Named("Person".into()) ]
+        [ "fn f(a: Int checking Positive) -> Int { a } let x = f(1);"          , "x"     , Type::Int                 ]
+        [ "fn f() -> Int checking Positive { 3 } let x = f();"                 , "x"     , Type::Checked(Refinement { base: Box::new(Type::Int), check: "Positive".into(), args: vec![] }) ]
+        [ "mut x: Int checking Positive = 1; x = 2;"                           , "x"     , Type::Checked(Refinement { base: Box::new(Type::Int), check: "Positive".into(), args: vec![] }) ]
+        [ "let x: Int checking Between(-1, 1) = -1;"                           , "x"     , Type::Checked(Refinement { base: Box::new(Type::Int), check: "Between".into(), args: vec![Literal::Int(-1), Literal::Int(1)] }) ]
+        [ "let x: Int checking R(1, \"s\", true) = 1;"                         , "x"     , Type::Checked(Refinement { base: Box::new(Type::Int), check: "R".into(), args: vec![Literal::Int(1), Literal::String("s".into()), Literal::Bool(true)] }) ]
+        [ "let x: Int checking Positive checking Between(0, 2) = 1;"           , "x"     , Type::Checked(Refinement { base: Box::new(Type::Checked(Refinement { base: Box::new(Type::Int), check: "Positive".into(), args: vec![] })), check: "Between".into(), args: vec![Literal::Int(0), Literal::Int(2)] }) ]
+        [ "check even<Int> { match value { 0 => okay, succ(0) => error, succ(succ(n)) => even(n), _ => error } }
+           let x: Int checking Even = 4;"                                      , "x"     , Type::Checked(Refinement { base: Box::new(Type::Int), check: "Even".into(), args: vec![] }) ]
+        [ "check even_ish<Int> { value % 2 == 0 } let x: Int checking EvenIsh = 4;", "x"  , Type::Checked(Refinement { base: Box::new(Type::Int), check: "EvenIsh".into(), args: vec![] }) ]
+        [ "check both<Int>(lower: Int) { positive(value) && { let l = lower; value > l } }
+           let x: Int checking Both(1) = 2;"                                   , "x"     , Type::Checked(Refinement { base: Box::new(Type::Int), check: "Both".into(), args: vec![Literal::Int(1)] }) ]
+    )]
+    fn well_typed_refinements(src: &str, name: &str, expected: Type) {
+        let program = util::check_with_checks(src).unwrap();
+        assert_eq!(Some(&expected), program.type_of(name))
+    }
+
+    #[test_with_parameters(
+        [ src                                                    , message                                                   , column ]
+        [ "fn f(a: Int checking Positive) {} let y = 1; f(y);"   , "Mismatched types: expected Int checking Positive, found Int", 48 ]
+        [ "fn f(a: Int checking R(-1, \"s\", true)) {} let y = 1; f(y);", "Mismatched types: expected Int checking R(-1, \"s\", true), found Int", 56 ]
+        [ "fn f(a: Int checking P) -> Int checking Q { a }"      , "Mismatched types: expected Int checking Q, found Int checking P", 45 ]
+        [ "fn f(a: Int checking P) -> Bool { a }"                , "Mismatched types: expected Bool, found Int checking P"   , 35     ]
+        [ "let x: Int checking R(1 + 1) = 1;"                    , "The arguments of a check must be literals"               , 23     ]
+        [ "fn f(a: Nope checking R) {}"                          , "Unknown type Nope"                                       , 9      ]
+        [ "record Person { age: Int checking StrictlyBetween(0, 130) }
+           let p = Person { age: 200 };"                         , "200 fails the check StrictlyBetween(0, 130)"             , 34     ]
+        [ "let x: String checking NonEmpty = \"\";"              , "\"\" fails the check NonEmpty"                           , 35     ]
+        [ "fn f(a: Int checking Positive) {} f(0);"              , "0 fails the check Positive"                              , 37     ]
+        [ "fn f() -> Int checking Positive { -1 }"               , "-1 fails the check Positive"                             , 35     ]
+        [ "mut x: Int checking Positive = 1; x = 0;"             , "0 fails the check Positive"                              , 39     ]
+        [ "let x: Int checking Positive checking Between(0, 2) = 0;", "0 fails the check Positive"                           , 55     ]
+        [ "let x: Int checking Nope = 1;"                        , "Unknown check Nope"                                      , 21     ]
+        [ "let x: String checking Positive = \"a\";"             , "Positive checks values of type Int, not String"          , 24     ]
+        [ "let x: Int checking Between(1) = 1;"                  , "Between expects 2 argument(s) but was given 1"           , 8      ]
+        [ "let x: Int checking Between(1, \"a\") = 1;"           , "Mismatched types: expected Int, found String"            , 32     ]
+        [ "check c<Int> { 1 }"                                   , "Mismatched types: expected Bool, found Int"              , 16     ]
+        [ "check c<Int> { value / 0 == 1 } let x: Int checking C = 1;", "Only non-zero Int literals can be divided by within a check", 22 ]
+        [ "check c<Int>(d: Int) { value % d == 0 }"              , "Only non-zero Int literals can be divided by within a check", 30 ]
+        [ "check c<Int> { match value { 0 => okay } } let x: Int checking C = 1;", "Matches within a check need an arm which matches any value", 16 ]
+        [ "enum E { a, b } check c<E> { match value { a => okay } }", "Matches within a check need an arm which matches any value", 30 ]
+        [ "check c<Int> { mut x = 1; okay }"                     , "Mutable bindings are not allowed within a check"         , 20     ]
+        [ "check c<Int> { fn f() {} okay }"                      , "Declarations are not allowed within a check"             , 19     ]
+        [ "check c<Int> { print_ln(value); okay }"               , "Only checks can be called from within a check"           , 16     ]
+        [ "check c<Int> { let f = positive; okay }"              , "The check positive can only be called within a check"   , 24     ]
+        [ "check c<Int> { (value, positive) == (1, positive) }"  , "The check positive can only be called within a check"   , 24     ]
+        [ "check c<Int>(positive: Int) { let p = positive; positive(value) }", "Only checks can be called from within a check", 49 ]
+        [ "check c<Int> { while false { } okay }"                , "`while` loops are not allowed within a check"            , 16     ]
+        [ "check c<Int> { value.to_string() == \"1\" }"          , "Method calls are not allowed within a check"             , 22     ]
+        [ "record P { a: Int } check c<Int> { P { a: value }.a > 0 }", "Record literals are not allowed within a check"   , 36     ]
+        [ "enum E { a } check c<Int> { E.a == E.a } let x: Int checking C = 1;", "Running the check C on 1 failed: The variant a cannot be evaluated at compile time", 66 ]
+        [ "check c<Int> { value + \"a\" == 1 } let x: Int checking C = 1;", "Operator `+` cannot be applied to Int and String", 22 ]
+        [ "check b<Int> { value + \"a\" == 1 } check a<Int> { b(value) } let x: Int checking A = 1;", "Operator `+` cannot be applied to Int and String", 22 ]
+        [ "check strictly_greater_than<Int>(lower: Int) {
+             match (value, lower) { succ(_), 0 => okay, 0, _ => error, succ(v), succ(l) => strictly_greater_than(v, l), _ => error }
+           } let x: Int checking StrictlyGreaterThan(3) = 2;"    , "2 fails the check StrictlyGreaterThan(3)"                , 59     ]
+        [ "check c<Int> { match value { 0 => okay, succ(n) => c(n), _ => error } }
+           let x: Int checking C = 10000000;"                    , "Running the check C on 10000000 failed: Maximum call depth exceeded", 36 ]
+        [ "fn f() { check c<Int> { value > 0 } } let x: Int checking C = 1;", "Checks can only be declared at the top level"     , 16     ]
+        [ "check c<Int> { value > 0 } check c<Int> { okay } let x: Int checking C = 0;", "The check c is already declared"      , 34     ]
+        [ "let limit = 1; check c<Int> { value > limit }"        , "Undefined identifier limit"                              , 39     ]
+    )]
+    fn ill_typed_refinements(src: &str, message: &str, column: usize) {
+        let errs = util::check_with_checks(src).unwrap_err();
+        assert_eq!(message, errs[0].message);
+        assert_eq!(column, errs[0].span.column);
+    }
+
+    #[test_with_parameters(
+        [ src ]
+        [ "check strictly_greater_than<Int>(lower: Int) {
+             match (value, lower) { succ(_), 0 => okay, 0, _ => error, succ(v), succ(l) => strictly_greater_than(v, l), _ => error }
+           }
+           let x: Int checking StrictlyGreaterThan(3) = 5;" ]
+        [ "check is_even<Int> { match value { 0 => okay, succ(n) => is_odd(n), _ => error } }
+           check is_odd<Int> { match value { 0 => error, succ(n) => is_even(n), _ => error } }
+           let x: Int checking IsOdd = 3;" ]
+        [ "check at_most<Int>(upper: Int) { match (value, upper) { (0, _) => okay, (succ(v), succ(u)) | (succ(succ(v)), u) => at_most(v, u), _ => error } }" ]
+        [ "enum List { empty, cons(head: Int, tail: List) }
+           check all_positive<List> { match value { cons(h, t) => h > 0 && all_positive(t), _ => okay } }" ]
+        [ "record Tree { label: Int, children: Forest } enum Forest { none, some(tree: Tree, rest: Forest) }
+           check tree_ok<Tree> { value.label > 0 && forest_ok(value.children) }
+           check forest_ok<Forest> { match value { some(t, rest) => tree_ok(t) && forest_ok(rest), _ => okay } }" ]
+        [ "check c<Int>(n: Int) { match (value, n) { succ(v), succ(m) => { let w = v; c(w, n) && c(v, m) }, _ => okay } }" ]
+    )]
+    fn terminating_checks_are_accepted(src: &str) {
+        util::check(src).unwrap();
+    }
+
+    #[test_with_parameters(
+        [ src                                                                  , message                                          , line, column ]
+        [ "check c<Int> { c(value) } let x: Int checking C = 1;"               , "none of its arguments are structurally smaller" , 1   , 16     ]
+        [ "check c<Int> { match value { succ(n) => c(n + 1), _ => okay } }"    , "none of its arguments are structurally smaller" , 1   , 41     ]
+        [ "check c<Int> { let v = value; c(v) }"                               , "none of its arguments are structurally smaller" , 1   , 31     ]
+        [ "check c<Int> { match value { succ(n) | n => c(n) } }"               , "none of its arguments are structurally smaller" , 1   , 45     ]
+        [ "check c<Int> { match value { succ(n) => { let n = value; c(n) }, _ => okay } }", "none of its arguments are structurally smaller", 1, 58 ]
+        [ "check c<Int>(n: Int) {
+             match (value, n) { succ(v), _ => c(v, n + 1), _, succ(m) => c(value + 1, m), _ => okay }
+           }"                                                                  , "it doesn't make the same argument smaller as the other recursive calls", 2, 74 ]
+        [ "check a<Int> { match value { succ(n) => b(n), _ => okay } }
+           check b<Int> { a(value) }"                                          , "none of its arguments are structurally smaller" , 2   , 27     ]
+    )]
+    fn nonterminating_checks_are_rejected(src: &str, message: &str, line: usize, column: usize) {
+        let errs = util::check(src).unwrap_err();
+        assert_eq!(1, errs.len());
+        assert!(
+            errs[0].message.ends_with(message),
+            "Unexpected error {}",
+            errs[0]
+        );
+        assert_eq!((line, column), (errs[0].span.line, errs[0].span.column));
+    }
+
+    #[test_with_parameters(
+        [ src ]
+        [ "fn f(d: Int) -> Int { if d != 0 { divide(1, d) } else { 0 } }" ]
+        [ "fn f(d: Int) -> Int { if 0 != d { divide(1, d) } else { 0 } }" ]
+        [ "fn f(d: Int) -> Int { if d == 0 { 0 } else { divide(1, d) } }" ]
+        [ "fn f(d: Int) -> Int { if !(d == 0) { divide(1, d) } else { 0 } }" ]
+        [ "fn f(d: Int) -> Int { if d > 0 && d < 10 { divide(1, d) } else { 0 } }" ]
+        [ "fn f(d: Int) -> Int { if d <= 0 || d >= 10 { 0 } else { divide(1, d) } }" ]
+        [ "fn f(d: Int) -> Int { if non_zero(d) { divide(1, d) } else { 0 } }" ]
+        [ "fn f(d: Int) -> Int { match d { 0 => 0, _ => divide(1, d) } }" ]
+        [ "fn f(d: Int) -> Int { match d { 0 => 0, n => divide(1, n) } }" ]
+        [ "fn f(d: Int) -> Int { match d { 0 => 0, succ(_) => divide(1, d), _ => 0 } }" ]
+        [ "fn f(x: Int) { if x < 5 { let y: Int checking StrictlyLessThan(5) = x; } }" ]
+        [ "fn f(x: Int) { if x > 0 { let y: Int checking Between(1, 3) = 2; let z: positive Int = x; } }" ]
+        [ "fn f(x: Int) { if 0 < x && x <= 100 { let y: Int checking Between(1, 100) = x; } }" ]
+        [ "fn f(x: Int) { if between(x, 1, 5) { let y: Int checking Between(1, 5) = x; } }" ]
+        [ "fn f(x: Int checking Positive) { if x != 3 { let y: Int checking Positive = x; } }" ]
+        [ "fn h(n: Int) -> Int checking Positive { if n > 0 { n } else { 1 } }" ]
+        [ "fn h(n: Int) -> Int checking Positive { if n > 0 { let m: Int checking Positive = n; m } else { 1 } }" ]
+        [ "fn h(n: Int) -> Int checking Positive { match n { 0 => 1, m => if m > 0 { m } else { 2 } } }" ]
+        [ "fn h(n: Int) { let p: Int checking Positive = { let m = n; if m < 1 { 1 } else { m } }; }" ]
+        [ "fn h(n: Int) { divide(1, if n != 0 { n } else { 1 }); }" ]
+    )]
+    fn narrowed_bindings_are_accepted(src: &str) {
+        util::check_with_checks(&format!("{}\n{}", util::NARROWING, src)).unwrap();
+    }
+
+    #[test_with_parameters(
+        [ src                                                                   , message                                                           , column ]
+        [ "fn f(d: Int) -> Int { if d == 0 { divide(1, d) } else { 0 } }"        , "Mismatched types: expected Int checking non_zero, found Int"     , 45 ]
+        [ "fn h(n: Int) -> Int checking Positive { if n > 0 { n } else { 0 } }"  , "0 fails the check Positive"                                      , 63 ]
+        [ "fn h(n: Int) -> Int checking Positive { if n < 0 { 1 } else { n } }"  , "Mismatched types: expected Int checking Positive, found Int"     , 63 ]
+        [ "fn f(d: Int) -> Int { if d != 0 { 0 } else { divide(1, d) } }"        , "Mismatched types: expected Int checking non_zero, found Int"     , 56 ]
+        [ "fn f(d: Int) -> Int { if d < 10 { divide(1, d) } else { 0 } }"        , "Mismatched types: expected Int checking non_zero, found Int"     , 45 ]
+        [ "fn f(d: Int) -> Int { if d != 0 || d > 5 { divide(1, d) } else { 0 } }", "Mismatched types: expected Int checking non_zero, found Int"     , 54 ]
+        [ "fn f(d: Int) -> Int { if non_zero(d) { 0 } else { divide(1, d) } }"   , "Mismatched types: expected Int checking non_zero, found Int"     , 61 ]
+        [ "fn f(d: Int) -> Int { match d { 0 => divide(1, d), _ => 0 } }"         , "Mismatched types: expected Int checking non_zero, found Int"     , 48 ]
+        [ "fn f(d: Int) -> Int { match d { succ(d) => divide(1, d), _ => 0 } }"   , "Mismatched types: expected Int checking non_zero, found Int"     , 54 ]
+        [ "fn f(d: Int) -> Int { fn non_zero(x: Int) -> Bool { true } if non_zero(d) { divide(1, d) } else { 0 } }", "Mismatched types: expected Int checking non_zero, found Int", 87 ]
+        [ "fn f(d: Int) -> Int { let non_zero = positive; if non_zero(d) { divide(1, d) } else { 0 } }", "Mismatched types: expected Int checking non_zero, found Int", 75 ]
+        [ "fn f(d: Int) -> Int { mut e = d; if e != 0 { divide(1, e) } else { 0 } }", "Mismatched types: expected Int checking non_zero, found Int"   , 56 ]
+        [ "fn f(x: Int) { if x < 6 { let y: Int checking StrictlyLessThan(5) = x; } }", "Mismatched types: expected Int checking StrictlyLessThan(5), found Int", 69 ]
+        [ "fn f(x: Int) { if x < 5 { 0 } else { 1 }; let y: Int checking StrictlyLessThan(5) = x; }", "Mismatched types: expected Int checking StrictlyLessThan(5), found Int", 85 ]
+        [ "fn f(x: Int) { if x < 5 { let y: Int checking P = x; } }"             , "Mismatched types: expected Int checking P, found Int"            , 51 ]
+        [ "fn f(x: Int) { if non_zero(x, 1) { 0 } else { 1 }; }"                 , "non_zero expects 1 argument(s) but was given 2"             , 19 ]
+    )]
+    fn unnarrowed_bindings_are_rejected(src: &str, message: &str, column: usize) {
+        let errs = util::check_with_checks(&format!("{}\n{}", util::NARROWING, src)).unwrap_err();
+        assert_eq!(message, errs[0].message);
+        assert_eq!(column, errs[0].span.column);
+    }
+
+    #[test]
+    fn reports_every_ill_typed_statement() {
+        let src = "let a: Int = \"a\";
+            fn f() -> Bool { 1 }
+            let b = a + 1;
+            let c = d;
+            c;";
+        let errs = util::check(src).unwrap_err();
+        let messages: Vec<String> = errs.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            vec![
+                "1:14: Mismatched types: expected Int, found String\n1:8: note: the expected type comes from here",
+                "2:30: Mismatched types: expected Bool, found Int\n2:23: note: the expected type comes from here",
+                "4:21: Undefined identifier d",
+                "5:13: Undefined identifier c",
+            ],
+            messages
+        );
+    }
+
+    #[test_with_parameters(
+        [ src                                          , line, column, origin_line, origin_column ]
+        [ "mut result = \"\";\nresult = 1;"          , 2   , 10    , 1          , 14            ]
+        [ "let x: Int = \"a\";"                       , 1   , 14    , 1          , 8             ]
+        [ "mut x: Int = 1;\nx = true;"                 , 2   , 5     , 1          , 8             ]
+        [ "fn f() -> String {\n  1\n}"                , 2   , 3     , 1          , 11            ]
+        [ "if true { 1 } else { \"a\" };"             , 1   , 20    , 1          , 11            ]
+        [ "match 1 {\n  0 => true,\n  _ => 1,\n};"   , 3   , 8     , 2          , 8             ]
+        [ "let x = \"a\";\nlet y = x + 1;"            , 2   , 11    , 1          , 9             ]
+        [ "let x = 1;\nlet y = true && x;"            , 2   , 14    , 1          , 9             ]
+        [ "fn f(a: Int) {}\nlet s = \"a\";\nf(s);"   , 3   , 3     , 1          , 9             ]
+        [ "fn f(a: Int) -> Bool {\n  a && true\n}"     , 2   , 5     , 1          , 9             ]
+    )]
+    fn conflicting_constraints_report_both_locations(
+        src: &str,
+        line: usize,
+        column: usize,
+        origin_line: usize,
+        origin_column: usize,
+    ) {
+        let errs = util::check(src).unwrap_err();
+        let origin = errs[0].origin.expect("Error should have an origin");
+        assert_eq!((line, column), (errs[0].span.line, errs[0].span.column));
+        assert_eq!((origin_line, origin_column), (origin.line, origin.column));
+    }
+
+    #[test]
+    fn infers_the_types_of_unannotated_bindings() {
+        let src = "fn fizz_buzz(i: Int) -> String {
+                mut result = \"\";
+                if i % 3 == 0 {
+                    result = result + \"Fizz\";
+                }
+                if i % 5 == 0 {
+                    result = result + \"Buzz\";
+                }
+                if result.is_empty() {
+                    result = i.to_string();
+                }
+                result
+            }
+            let fifteen = fizz_buzz(15);";
+        let program = util::check(src).unwrap();
+        assert_eq!(Some(&Type::String), program.type_of("fifteen"));
+    }
+
+    #[test_with_parameters(
+        [ src                                                  ]
+        [ include_str!("../../../docs/examples/hello_world.ei") ]
+        [ include_str!("../../../docs/examples/hello_fn.ei")    ]
+        [ include_str!("../../../docs/examples/refinements.ei") ]
+        [ include_str!("../../../docs/examples/divide.ei")      ]
+    )]
+    fn examples_are_well_typed(src: &str) {
+        util::check(src).unwrap();
+    }
+
+    mod util {
+        use super::*;
+
+        pub(crate) const CHECKS: &str = "check positive<Int> { value > 0 }
+            check non_empty<String> { match value { \"\" => error, _ => okay } }
+            check p<Int> { okay }
+            check q<Int> { okay }
+            check r<Int>(a: Int, b: String, c: Bool) { value == a && b != \"\" && c }
+            check between<Int>(lower: Int, upper: Int) { lower <= value && value <= upper }
+            check strictly_between<Int>(lower: Int, upper: Int) { lower < value && value < upper }";
+
+        pub(crate) const NARROWING: &str = "check non_zero<Int> { value != 0 }
+            check strictly_less_than<Int>(upper: Int) { value < upper }
+            fn divide(n: Int, d: non_zero Int) -> Int { n / d }";
+
+        pub(crate) fn check(src: &str) -> Result<TypedProgram, Vec<TypeError>> {
+            let program = parse(token_stream(src)).expect("Test program should parse");
+            typecheck(program)
+        }
+
+        /// Checks `src` with the checks in `CHECKS` declared, keeping the
+        /// columns of any errors in `src`'s first line the same.
+        pub(crate) fn check_with_checks(src: &str) -> Result<TypedProgram, Vec<TypeError>> {
+            check(&format!("{}\n{}", CHECKS, src))
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use ast::{Expr, Literal};
+use token::{Token, TokenKind};
+
+use crate::{check_arg, checks::Check};
+
+/// What's known about an Int binding from the comparisons which must have
+/// held for the program to reach some point, e.g. within the branches of
+/// `if x < 5 { .. } else { .. }`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Bounds {
+    lower: Option<i64>,
+    upper: Option<i64>,
+    excluded: Vec<i64>,
+}
+
+impl Bounds {
+    /// Records that `value op c` holds.
+    pub(crate) fn add(&mut self, op: &TokenKind, c: i64) {
+        use TokenKind::*;
+
+        let (lower, upper) = match op {
+            Less => (None, Some(c.saturating_sub(1))),
+            LessEqual => (None, Some(c)),
+            Greater => (Some(c.saturating_add(1)), None),
+            GreaterEqual => (Some(c), None),
+            EqualEqual => (Some(c), Some(c)),
+            _ => {
+                self.excluded.push(c);
+                return;
+            }
+        };
+        if let Some(l) = lower {
+            self.lower = Some(self.lower.map_or(l, |b| b.max(l)));
+        }
+        if let Some(u) = upper {
+            self.upper = Some(self.upper.map_or(u, |b| b.min(u)));
+        }
+    }
+
+    /// Whether `value op c` must hold.
+    pub(crate) fn implies(&self, op: &TokenKind, c: i64) -> bool {
+        use TokenKind::*;
+
+        let below = |u: Option<i64>, c: i64| u.is_some_and(|u| u < c);
+        let above = |l: Option<i64>, c: i64| l.is_some_and(|l| l > c);
+        match op {
+            Less => below(self.upper, c),
+            LessEqual => below(self.upper, c.saturating_add(1)),
+            Greater => above(self.lower, c),
+            GreaterEqual => above(self.lower, c.saturating_sub(1)),
+            EqualEqual => self.lower == Some(c) && self.upper == Some(c),
+            _ => below(self.upper, c) || above(self.lower, c) || self.excluded.contains(&c),
+        }
+    }
+}
+
+/// Something which is known about a binding in part of the program.
+pub(crate) enum Narrowing {
+    /// `name op value` holds.
+    Comparison {
+        name: String,
+        op: TokenKind,
+        value: i64,
+    },
+    /// `check(name, args)` passes.
+    Check {
+        name: String,
+        check: Token,
+        args: Vec<Literal>,
+    },
+}
+
+/// What's known about bindings wherever `condition` evaluates to `holds`.
+pub(crate) fn narrowings(condition: &Expr, holds: bool) -> Vec<Narrowing> {
+    match condition {
+        Expr::Unary { op, right } if op.kind == TokenKind::Bang => narrowings(right, !holds),
+        Expr::Binary { left, op, right } => match (&op.kind, holds) {
+            (TokenKind::And, true) | (TokenKind::Or, false) => {
+                let mut both = narrowings(left, holds);
+                both.extend(narrowings(right, holds));
+                both
+            }
+            (TokenKind::And | TokenKind::Or, _) => Vec::new(),
+            (kind, _) => comparison(left, kind, right, holds).into_iter().collect(),
+        },
+        Expr::FunctionApplication { callee, args, .. } if holds => {
+            let (Expr::Identifier { name: check }, Some(Expr::Identifier { name })) =
+                (callee.as_ref(), args.first())
+            else {
+                return Vec::new();
+            };
+            let Ok(args) = args[1..].iter().map(check_arg).collect() else {
+                return Vec::new();
+            };
+            vec![Narrowing::Check {
+                name: name.lexeme.clone(),
+                check: check.clone(),
+                args,
+            }]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The narrowing from a comparison between a binding & an Int literal.
+fn comparison(left: &Expr, op: &TokenKind, right: &Expr, holds: bool) -> Option<Narrowing> {
+    let (name, op, value) = match (left, check_arg(left), right, check_arg(right)) {
+        (Expr::Identifier { name }, _, _, Ok(Literal::Int(value))) => (name, op.clone(), value),
+        (_, Ok(Literal::Int(value)), Expr::Identifier { name }, _) => (name, flipped(op), value),
+        _ => return None,
+    };
+    let op = if holds { op } else { negated(&op) };
+    is_comparison(&op).then(|| Narrowing::Comparison {
+        name: name.lexeme.clone(),
+        op,
+        value,
+    })
+}
+
+fn is_comparison(op: &TokenKind) -> bool {
+    use TokenKind::*;
+
+    matches!(
+        op,
+        Less | LessEqual | Greater | GreaterEqual | EqualEqual | BangEqual
+    )
+}
+
+/// The comparison which holds with its operands swapped, e.g. `5 > x` is
+/// the same as `x < 5`.
+fn flipped(op: &TokenKind) -> TokenKind {
+    use TokenKind::*;
+
+    match op {
+        Less => Greater,
+        LessEqual => GreaterEqual,
+        Greater => Less,
+        GreaterEqual => LessEqual,
+        op => op.clone(),
+    }
+}
+
+/// The comparison which holds when `op` doesn't.
+fn negated(op: &TokenKind) -> TokenKind {
+    use TokenKind::*;
+
+    match op {
+        Less => GreaterEqual,
+        LessEqual => Greater,
+        Greater => LessEqual,
+        GreaterEqual => Less,
+        EqualEqual => BangEqual,
+        BangEqual => EqualEqual,
+        op => op.clone(),
+    }
+}
+
+/// An operand of a comparison within a check's body.
+pub(crate) enum Operand {
+    Literal(i64),
+    /// The check's parameter at this position.
+    Param(usize),
+}
+
+/// The comparisons with `value` which a check consists of, if its body is
+/// just a conjunction of them, e.g. `lower < value && value < upper`.
+pub(crate) fn summary(check: &Check) -> Option<Vec<(TokenKind, Operand)>> {
+    if !check.decl.body.stmts.is_empty() {
+        return None;
+    }
+    let mut comparisons = Vec::new();
+    summarise(check, check.decl.body.tail.as_deref()?, &mut comparisons)?;
+    Some(comparisons)
+}
+
+fn summarise(check: &Check, e: &Expr, comparisons: &mut Vec<(TokenKind, Operand)>) -> Option<()> {
+    let Expr::Binary { left, op, right } = e else {
+        return match e {
+            Expr::Identifier { name } if name.lexeme == "okay" => Some(()),
+            _ => None,
+        };
+    };
+    if op.kind == TokenKind::And {
+        summarise(check, left, comparisons)?;
+        return summarise(check, right, comparisons);
+    }
+
+    let operand = |e: &Expr| match (e, check_arg(e)) {
+        (_, Ok(Literal::Int(i))) => Some(Operand::Literal(i)),
+        (Expr::Identifier { name }, _) => check
+            .decl
+            .params
+            .iter()
+            .position(|p| p.name.lexeme == name.lexeme)
+            .map(Operand::Param),
+        _ => None,
+    };
+    let is_value = |e: &Expr| matches!(e, Expr::Identifier { name } if name.lexeme == "value");
+    let (op, other) = if is_value(left) {
+        (op.kind.clone(), right)
+    } else if is_value(right) {
+        (flipped(&op.kind), left)
+    } else {
+        return None;
+    };
+    if !is_comparison(&op) {
+        return None;
+    }
+    comparisons.push((op, operand(other)?));
+    Some(())
+}
@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+
+use token::Span;
+
+use crate::{
+    narrowing::Bounds,
+    types::{FnType, Type, TypeVar},
+};
+
+/// The names & types of a record's or variant's fields, in declaration order.
+pub(crate) type Fields = Vec<(String, Type)>;
+
+/// What's known about a type declared in the program.
+pub(crate) enum TypeDef {
+    Record {
+        fields: Vec<(String, Type)>,
+    },
+    Enum {
+        variants: Vec<(String, Vec<(String, Type)>)>,
+    },
+    Interface {
+        methods: Vec<(String, FnType)>,
+    },
+}
+
+#[derive(Clone)]
+pub(crate) struct Binding {
+    pub(crate) ty: Type,
+    /// Type variables in `ty` which are instantiated afresh each time the
+    /// binding is used, e.g. so that `print_ln` can be given any value.
+    pub(crate) quantified: Vec<TypeVar>,
+    pub(crate) mutable: bool,
+    /// The syntax which determined the binding's type, if it's somewhere in
+    /// the program.
+    pub(crate) origin: Option<Span>,
+    /// The syntax which determined the type of each of the binding's
+    /// parameters, if it's a function declared in the program.
+    pub(crate) param_origins: Vec<Span>,
+    /// Whether the binding is a check, rather than a function which could
+    /// return anything.
+    pub(crate) is_check: bool,
+    /// What's known about the binding's value, if it's an Int.
+    pub(crate) bounds: Bounds,
+}
+
+impl Binding {
+    pub(crate) fn new(ty: Type) -> Binding {
+        Binding {
+            ty,
+            quantified: Vec::new(),
+            mutable: false,
+            origin: None,
+            param_origins: Vec::new(),
+            is_check: false,
+            bounds: Bounds::default(),
+        }
+    }
+}
+
+/// The bindings & types which are visible at some point in the program,
+/// innermost scope last.
+pub(crate) struct Scopes {
+    scopes: Vec<Scope>,
+}
+
+#[derive(Default)]
+struct Scope {
+    values: HashMap<String, Binding>,
+    types: HashMap<String, TypeDef>,
+    /// Whether the values of enclosing scopes are hidden from this one.
+    isolated: bool,
+}
+
+impl Scopes {
+    pub(crate) fn new() -> Scopes {
+        Scopes {
+            scopes: vec![Scope::default()],
+        }
+    }
+
+    pub(crate) fn push(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    /// Pushes a scope from which only the types, & not the values, of the
+    /// enclosing scopes can be seen.
+    pub(crate) fn push_isolated(&mut self) {
+        self.scopes.push(Scope {
+            isolated: true,
+            ..Scope::default()
+        });
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Binds `name` in the innermost scope, shadowing any existing binding.
+    pub(crate) fn define<S: Into<String>>(&mut self, name: S, binding: Binding) {
+        self.innermost().values.insert(name.into(), binding);
+    }
+
+    pub(crate) fn define_type<S: Into<String>>(&mut self, name: S, def: TypeDef) {
+        self.innermost().types.insert(name.into(), def);
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Binding> {
+        for s in self.scopes.iter().rev() {
+            if let Some(binding) = s.values.get(name) {
+                return Some(binding);
+            }
+            if s.isolated {
+                break;
+            }
+        }
+        None
+    }
+
+    pub(crate) fn get_type(&self, name: &str) -> Option<&TypeDef> {
+        self.scopes.iter().rev().find_map(|s| s.types.get(name))
+    }
+
+    /// Whether the innermost scope is the outermost one.
+    pub(crate) fn is_global(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    /// The names of the variants of every visible enum.
+    pub(crate) fn variant_names(&self) -> HashSet<String> {
+        self.scopes
+            .iter()
+            .flat_map(|s| s.types.values())
+            .filter_map(|def| match def {
+                TypeDef::Enum { variants } => Some(variants),
+                _ => None,
+            })
+            .flat_map(|variants| variants.iter().map(|(name, _)| name.clone()))
+            .collect()
+    }
+
+    /// Every visible binding, including shadowed ones.
+    pub(crate) fn bindings(&self) -> impl Iterator<Item = &Binding> {
+        self.scopes.iter().flat_map(|s| s.values.values())
+    }
+
+    /// The bindings of the outermost scope.
+    pub(crate) fn globals(&self) -> impl Iterator<Item = (&String, &Type)> {
+        self.scopes[0].values.iter().map(|(name, b)| (name, &b.ty))
+    }
+
+    fn innermost(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("There is always a global scope")
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    checks::declared_name,
+    types::{FnType, Refinement, Type, TypeVar},
+};
+
+/// The types which inference has found for each type variable so far.
+#[derive(Default)]
+pub(crate) struct Substitution {
+    vars: Vec<Option<Type>>,
+}
+
+impl Substitution {
+    /// A type variable which hasn't been constrained yet.
+    pub(crate) fn fresh(&mut self) -> Type {
+        self.vars.push(None);
+        Type::Var(self.vars.len() - 1)
+    }
+
+    /// `t` with every solved type variable replaced by its solution.
+    pub(crate) fn apply(&self, t: &Type) -> Type {
+        match t {
+            Type::Var(v) => match &self.vars[*v] {
+                Some(solution) => self.apply(solution),
+                None => t.clone(),
+            },
+            Type::Fn(f) => Type::Fn(FnType::new(
+                f.params.iter().map(|p| self.apply(p)).collect(),
+                self.apply(&f.ret),
+            )),
+            Type::Tuple(elements) => Type::Tuple(elements.iter().map(|e| self.apply(e)).collect()),
+            Type::Checked(r) => Type::Checked(Refinement {
+                base: Box::new(self.apply(&r.base)),
+                ..r.clone()
+            }),
+            _ => t.clone(),
+        }
+    }
+
+    /// Constrains `a` & `b` to be the same type, returning whether that's
+    /// possible.
+    pub(crate) fn unify(&mut self, a: &Type, b: &Type) -> bool {
+        match (self.apply(a), self.apply(b)) {
+            (Type::Var(u), Type::Var(v)) if u == v => true,
+            (Type::Var(v), t) | (t, Type::Var(v)) => {
+                // Solving `v` as a type containing itself would make that
+                // type infinite.
+                if t.mentions(v) {
+                    return false;
+                }
+                self.vars[v] = Some(t);
+                true
+            }
+            (Type::Fn(f), Type::Fn(g)) => {
+                f.params.len() == g.params.len()
+                    && f.params
+                        .iter()
+                        .zip(&g.params)
+                        .all(|(p, q)| self.unify(p, q))
+                    && self.unify(&f.ret, &g.ret)
+            }
+            (Type::Tuple(xs), Type::Tuple(ys)) => {
+                xs.len() == ys.len() && xs.iter().zip(&ys).all(|(x, y)| self.unify(x, y))
+            }
+            // A check may be referred to by its declared name or in
+            // UpperCamelCase.
+            (Type::Checked(r), Type::Checked(q)) => {
+                declared_name(&r.check) == declared_name(&q.check)
+                    && r.args == q.args
+                    && self.unify(&r.base, &q.base)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Replaces each of `vars` within `t` with a fresh type variable.
+    pub(crate) fn instantiate(&mut self, t: &Type, vars: &[TypeVar]) -> Type {
+        let fresh: Vec<(TypeVar, Type)> = vars.iter().map(|v| (*v, self.fresh())).collect();
+        replace(&self.apply(t), &fresh)
+    }
+}
+
+fn replace(t: &Type, replacements: &[(TypeVar, Type)]) -> Type {
+    match t {
+        Type::Var(v) => replacements
+            .iter()
+            .find(|(u, _)| u == v)
+            .map_or_else(|| t.clone(), |(_, r)| r.clone()),
+        Type::Fn(f) => Type::Fn(FnType::new(
+            f.params.iter().map(|p| replace(p, replacements)).collect(),
+            replace(&f.ret, replacements),
+        )),
+        Type::Tuple(elements) => {
+            Type::Tuple(elements.iter().map(|e| replace(e, replacements)).collect())
+        }
+        Type::Checked(r) => Type::Checked(Refinement {
+            base: Box::new(replace(&r.base, replacements)),
+            ..r.clone()
+        }),
+        _ => t.clone(),
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::{HashMap, HashSet};
+
+use ast::{Block, Expr, Pattern, Stmt};
+use token::Span;
+
+use crate::{
+    checks::{Check, Checks},
+    TypeError,
+};
+
+/// Checks that running the check `name` always terminates.
+///
+/// Checks may call themselves, or each other, but only if there's an
+/// argument which every such recursive call makes structurally smaller,
+/// e.g. by passing on `n` from a `succ(n)` pattern, or a field of an enum
+/// variant or record. Every argument is finite, so the recursion has to end.
+pub(crate) fn check_termination(name: &str, checks: &Checks) -> Result<(), TypeError> {
+    let calls: HashMap<&str, Vec<Call>> = checks
+        .iter()
+        .map(|(n, check)| (n.as_str(), calls_in(check, checks)))
+        .collect();
+
+    // The checks which `name` calls, & which call it in turn.
+    let mut group: Vec<&str> = calls
+        .keys()
+        .copied()
+        .filter(|n| reaches(name, n, &calls) && reaches(n, name, &calls))
+        .collect();
+    group.sort_by_key(|n| {
+        let span = checks[*n].decl.name.span;
+        (span.line, span.column)
+    });
+
+    // The first call which leaves no argument that every recursive call in
+    // the group makes smaller is reported, when checking the check it's in.
+    let mut decreasing: Option<HashSet<usize>> = None;
+    for caller in &group {
+        for call in &calls[caller] {
+            if !group.contains(&call.callee.as_str()) {
+                continue;
+            }
+            let smaller = call.smaller_args();
+            let remaining: HashSet<usize> = match &decreasing {
+                Some(d) => d.intersection(&smaller).copied().collect(),
+                None => smaller.clone(),
+            };
+            if remaining.is_empty() {
+                if *caller != name {
+                    return Ok(());
+                }
+                let reason = if smaller.is_empty() {
+                    "none of its arguments are structurally smaller"
+                } else {
+                    "it doesn't make the same argument smaller as the other recursive calls"
+                };
+                return Err(TypeError::new(
+                    format!(
+                        "The recursive call to {} might never terminate, as {}",
+                        call.callee, reason
+                    ),
+                    call.span,
+                ));
+            }
+            decreasing = Some(remaining);
+        }
+    }
+    Ok(())
+}
+
+/// The names of the checks which `check` calls directly.
+pub(crate) fn callees(check: &Check, checks: &Checks) -> HashSet<String> {
+    calls_in(check, checks)
+        .into_iter()
+        .map(|call| call.callee)
+        .collect()
+}
+
+/// Whether `from` calls `to`, either directly or through other checks.
+fn reaches(from: &str, to: &str, calls: &HashMap<&str, Vec<Call>>) -> bool {
+    let mut visited = HashSet::new();
+    let mut pending = vec![from];
+    while let Some(caller) = pending.pop() {
+        for call in calls.get(caller).into_iter().flatten() {
+            if call.callee == to {
+                return true;
+            }
+            if visited.insert(call.callee.as_str()) {
+                pending.push(call.callee.as_str());
+            }
+        }
+    }
+    false
+}
+
+/// How a value within a check relates to the check's own arguments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Size {
+    /// The position of the argument which the value is part of, where
+    /// `value` is the first.
+    arg: usize,
+    /// Whether the value is strictly smaller than the whole argument.
+    smaller: bool,
+}
+
+impl Size {
+    fn whole(arg: usize) -> Size {
+        Size {
+            arg,
+            smaller: false,
+        }
+    }
+
+    fn part(self) -> Size {
+        Size {
+            smaller: true,
+            ..self
+        }
+    }
+}
+
+/// A call from within a check to another check, or to itself.
+struct Call {
+    callee: String,
+    args: Vec<Option<Size>>,
+    span: Span,
+}
+
+impl Call {
+    /// The positions of the arguments which are strictly smaller than the
+    /// caller's arguments at the same position.
+    fn smaller_args(&self) -> HashSet<usize> {
+        self.args
+            .iter()
+            .enumerate()
+            .filter(|(i, size)| **size == Some(Size::whole(*i).part()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// The calls to checks within the body of `check`.
+fn calls_in(check: &Check, checks: &Checks) -> Vec<Call> {
+    let mut args = HashMap::from([("value".to_string(), Some(Size::whole(0)))]);
+    for (i, param) in check.decl.params.iter().enumerate() {
+        args.insert(param.name.lexeme.clone(), Some(Size::whole(i + 1)));
+    }
+    let mut finder = CallFinder {
+        checks,
+        scopes: vec![args],
+        calls: Vec::new(),
+    };
+    finder.block(&check.decl.body);
+    finder.calls
+}
+
+/// What's known about the parts of a value being matched against.
+enum Shape {
+    Tuple(Vec<Shape>),
+    Value(Option<Size>),
+}
+
+/// Walks the body of a check, tracking which names are bound to (parts of)
+/// its arguments.
+struct CallFinder<'a> {
+    checks: &'a Checks,
+    scopes: Vec<HashMap<String, Option<Size>>>,
+    calls: Vec<Call>,
+}
+
+impl CallFinder<'_> {
+    fn block(&mut self, block: &Block) {
+        self.scopes.push(HashMap::new());
+        for s in &block.stmts {
+            match s {
+                Stmt::Expr { e } => self.expr(e),
+                Stmt::Let {
+                    name, initializer, ..
+                } => {
+                    self.expr(initializer);
+                    let size = self.size(initializer);
+                    self.define(&name.lexeme, size);
+                }
+                _ => {}
+            }
+        }
+        if let Some(tail) = &block.tail {
+            self.expr(tail);
+        }
+        self.scopes.pop();
+    }
+
+    fn expr(&mut self, e: &Expr) {
+        match e {
+            Expr::Identifier { .. } | Expr::Literal { .. } => {}
+            Expr::FunctionApplication { callee, args, span } => {
+                args.iter().for_each(|a| self.expr(a));
+                match callee.as_ref() {
+                    Expr::Identifier { name }
+                        if self.checks.contains_key(&name.lexeme)
+                            && self.lookup(&name.lexeme).is_none() =>
+                    {
+                        let args = args.iter().map(|a| self.size(a)).collect();
+                        self.calls.push(Call {
+                            callee: name.lexeme.clone(),
+                            args,
+                            span: *span,
+                        });
+                    }
+                    callee => self.expr(callee),
+                }
+            }
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Assign { value, .. } => self.expr(value),
+            Expr::Block { block } => self.block(block),
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                self.block(then_branch);
+                if let Some(e) = else_branch {
+                    self.expr(e);
+                }
+            }
+            Expr::While {
+                condition, body, ..
+            } => {
+                self.expr(condition);
+                self.block(body);
+            }
+            Expr::RecordLiteral { fields, .. } => fields.iter().for_each(|f| self.expr(&f.value)),
+            Expr::FieldAccess { object, .. } => self.expr(object),
+            Expr::MethodCall { receiver, args, .. } => {
+                self.expr(receiver);
+                args.iter().for_each(|a| self.expr(a));
+            }
+            Expr::Tuple { elements, .. } => elements.iter().for_each(|e| self.expr(e)),
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee);
+                let shape = self.shape(scrutinee);
+                for arm in arms {
+                    self.scopes.push(HashMap::new());
+                    self.bind(&arm.pattern, &shape);
+                    self.expr(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+        }
+    }
+
+    /// How `e` relates to the check's arguments, if it's one of them or a
+    /// part of one.
+    fn size(&self, e: &Expr) -> Option<Size> {
+        match e {
+            Expr::Identifier { name } => self.lookup(&name.lexeme).flatten(),
+            Expr::FieldAccess { object, .. } => self.size(object).map(Size::part),
+            _ => None,
+        }
+    }
+
+    fn shape(&self, e: &Expr) -> Shape {
+        match e {
+            Expr::Tuple { elements, .. } => {
+                Shape::Tuple(elements.iter().map(|e| self.shape(e)).collect())
+            }
+            _ => Shape::Value(self.size(e)),
+        }
+    }
+
+    /// Binds the names in `pattern`, which matches a value of the given
+    /// `shape`.
+    fn bind(&mut self, pattern: &Pattern, shape: &Shape) {
+        match (pattern, shape) {
+            (Pattern::Identifier { name }, Shape::Value(size)) => self.define(&name.lexeme, *size),
+            (Pattern::Identifier { name }, Shape::Tuple(_)) => self.define(&name.lexeme, None),
+            // The fields of a variant, including the Int before a `succ`,
+            // are smaller than the variant itself.
+            (
+                Pattern::Variant {
+                    fields: Some(fields),
+                    ..
+                },
+                shape,
+            ) => {
+                let size = match shape {
+                    Shape::Value(size) => size.map(Size::part),
+                    Shape::Tuple(_) => None,
+                };
+                for field in fields {
+                    self.bind(field, &Shape::Value(size));
+                }
+            }
+            (Pattern::Tuple { elements, .. }, Shape::Tuple(shapes))
+                if elements.len() == shapes.len() =>
+            {
+                for (element, shape) in elements.iter().zip(shapes) {
+                    self.bind(element, shape);
+                }
+            }
+            (Pattern::Tuple { elements, .. }, _) => {
+                for element in elements {
+                    self.bind(element, &Shape::Value(None));
+                }
+            }
+            (Pattern::Or { alternatives }, shape) => {
+                // A name is only known to be part of an argument if it is in
+                // every alternative.
+                let mut bound: Option<HashMap<String, Option<Size>>> = None;
+                for alternative in alternatives {
+                    self.scopes.push(HashMap::new());
+                    self.bind(alternative, shape);
+                    let names = self.scopes.pop().expect("A scope was just pushed");
+                    bound = Some(match bound {
+                        None => names,
+                        Some(b) => b
+                            .into_iter()
+                            .map(|(n, size)| {
+                                let agreed = names.get(&n).is_some_and(|s| *s == size);
+                                (n, if agreed { size } else { None })
+                            })
+                            .collect(),
+                    });
+                }
+                for (name, size) in bound.unwrap_or_default() {
+                    self.define(&name, size);
+                }
+            }
+            (Pattern::Variant { fields: None, .. }, _)
+            | (Pattern::Wildcard { .. }, _)
+            | (Pattern::Literal { .. }, _) => {}
+        }
+    }
+
+    fn define(&mut self, name: &str, size: Option<Size>) {
+        self.scopes
+            .last_mut()
+            .expect("Checks are walked within a scope")
+            .insert(name.to_string(), size);
+    }
+
+    /// The size of the value bound to `name`, if it's bound at all.
+    fn lookup(&self, name: &str) -> Option<Option<Size>> {
+        self.scopes.iter().rev().find_map(|s| s.get(name)).copied()
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+
+use ast::Literal;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    String,
+    Int,
+    Bool,
+    /// The type of expressions which don't produce a value.
+    Void,
+    Fn(FnType),
+    Tuple(Vec<Type>),
+    /// A record, enum or interface, referred to by the name it was declared
+    /// with.
+    Named(String),
+    /// The values of a type which pass some check. It's a strict subtype of
+    /// the type it refines, even if the check accepts every value.
+    Checked(Refinement),
+    /// A type which hasn't been inferred yet. Unification replaces it with
+    /// whatever type it's constrained to be.
+    Var(TypeVar),
+}
+
+pub type TypeVar = usize;
+
+/// `base checking check(args)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Refinement {
+    pub base: Box<Type>,
+    pub check: String,
+    pub args: Vec<Literal>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FnType {
+    pub params: Vec<Type>,
+    pub ret: Box<Type>,
+}
+
+impl FnType {
+    pub fn new(params: Vec<Type>, ret: Type) -> FnType {
+        FnType {
+            params,
+            ret: Box::new(ret),
+        }
+    }
+}
+
+impl Type {
+    /// The name which `impl` blocks for this type are declared against, if
+    /// it can have methods.
+    pub(crate) fn impl_name(&self) -> Option<&str> {
+        match self {
+            Type::String => Some("String"),
+            Type::Int => Some("Int"),
+            Type::Bool => Some("Bool"),
+            Type::Named(name) => Some(name),
+            Type::Checked(r) => r.base.impl_name(),
+            _ => None,
+        }
+    }
+
+    /// The type without any refinements, which values of this type can
+    /// always be used as.
+    pub fn unrefined(&self) -> &Type {
+        match self {
+            Type::Checked(r) => r.base.unrefined(),
+            t => t,
+        }
+    }
+
+    /// Whether the type variable `v` appears anywhere within this type.
+    pub(crate) fn mentions(&self, v: TypeVar) -> bool {
+        match self {
+            Type::Var(u) => *u == v,
+            Type::Fn(f) => f.params.iter().any(|p| p.mentions(v)) || f.ret.mentions(v),
+            Type::Tuple(elements) => elements.iter().any(|e| e.mentions(v)),
+            Type::Checked(r) => r.base.mentions(v),
+            _ => false,
+        }
+    }
+
+    /// Every type variable which appears within this type.
+    pub(crate) fn vars(&self, vars: &mut Vec<TypeVar>) {
+        match self {
+            Type::Var(v) if !vars.contains(v) => vars.push(*v),
+            Type::Fn(f) => {
+                f.params.iter().for_each(|p| p.vars(vars));
+                f.ret.vars(vars);
+            }
+            Type::Tuple(elements) => elements.iter().for_each(|e| e.vars(vars)),
+            Type::Checked(r) => r.base.vars(vars),
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::String => write!(f, "String"),
+            Type::Int => write!(f, "Int"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Void => write!(f, "Void"),
+            Type::Fn(fn_type) => write!(f, "{}", fn_type),
+            Type::Tuple(elements) => {
+                write!(f, "(")?;
+                write_list(f, elements)?;
+                write!(f, ")")
+            }
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Checked(r) => write!(f, "{} checking {}", r.base, r),
+            Type::Var(v) => write!(f, "?{}", v),
+        }
+    }
+}
+
+impl fmt::Display for Refinement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.check)?;
+        if !self.args.is_empty() {
+            write!(f, "(")?;
+            for (i, arg) in self.args.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", LiteralDisplay(arg))?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays a literal as it would be written in the source.
+pub(crate) struct LiteralDisplay<'a>(pub(crate) &'a Literal);
+
+impl fmt::Display for LiteralDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Literal::String(s) => write!(f, "{:?}", s),
+            Literal::Int(i) => write!(f, "{}", i),
+            Literal::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl fmt::Display for FnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fn(")?;
+        write_list(f, &self.params)?;
+        write!(f, ") -> {}", self.ret)
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, types: &[Type]) -> fmt::Result {
+    for (i, t) in types.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", t)?;
+    }
+    Ok(())
+}